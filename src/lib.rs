@@ -1,312 +1,4836 @@
-use anyhow::Result;
-use reqwest;
-use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use futures_util::Stream;
+#[cfg(feature = "net")]
+use reqwest::{header, StatusCode};
+#[cfg(feature = "net")]
+use serde::de::DeserializeOwned;
+use serde::{de, Deserialize, Deserializer, Serialize};
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use std::future::Future;
+#[cfg(feature = "csv")]
+use std::io::Write;
+#[cfg(feature = "net")]
+use std::time::Duration;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    io::Read,
+    iter::Sum,
+    ops::Add,
+    str::FromStr,
+};
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use std::{path::PathBuf, sync::Arc};
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+use tokio::{sync::Mutex, time::Instant};
 
-async fn get_latest_url(url: &str) -> Result<String> {
-    #[derive(Serialize, Deserialize)]
-    struct Resource {
-        coverage: String,
-        url: String,
+/// Not exhaustive, since the `net` and `csv` features add variants.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StimmtError {
+    #[cfg(feature = "net")]
+    Http(reqwest::Error),
+    Parse(serde_json::Error),
+    /// The CKAN package lists no resources.
+    NoResources,
+    /// Nothing exists at the URL, e.g. because there was no vote on that day.
+    NotFound(String),
+    Io(std::io::Error),
+    #[cfg(feature = "csv")]
+    Csv(csv::Error),
+    /// A geo level number that is not numeric.
+    InvalidGeoLevel(String),
+    /// The CKAN package lists no resource with this coverage.
+    CoverageNotFound(String),
+    /// CKAN answered with `success: false`, e.g. for an unknown package id.
+    Ckan(String),
+}
+
+impl fmt::Display for StimmtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "net")]
+            StimmtError::Http(err) => write!(f, "HTTP request failed: {err}"),
+            StimmtError::Parse(err) => write!(f, "failed to parse JSON: {err}"),
+            StimmtError::NoResources => write!(f, "no resources found"),
+            StimmtError::NotFound(url) => write!(f, "no data found at {url}"),
+            StimmtError::Io(err) => write!(f, "I/O error: {err}"),
+            #[cfg(feature = "csv")]
+            StimmtError::Csv(err) => write!(f, "failed to write CSV: {err}"),
+            StimmtError::InvalidGeoLevel(number) => write!(f, "invalid geo level number {number}"),
+            StimmtError::CoverageNotFound(coverage) => {
+                write!(f, "no resource found with coverage {coverage}")
+            }
+            StimmtError::Ckan(message) => write!(f, "CKAN request failed: {message}"),
+        }
     }
+}
 
-    #[derive(Serialize, Deserialize)]
-    struct Resources {
-        resources: Vec<Resource>,
+impl std::error::Error for StimmtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "net")]
+            StimmtError::Http(err) => Some(err),
+            StimmtError::Parse(err) => Some(err),
+            StimmtError::Io(err) => Some(err),
+            #[cfg(feature = "csv")]
+            StimmtError::Csv(err) => Some(err),
+            StimmtError::NoResources
+            | StimmtError::NotFound(_)
+            | StimmtError::InvalidGeoLevel(_)
+            | StimmtError::CoverageNotFound(_)
+            | StimmtError::Ckan(_) => None,
+        }
     }
+}
 
-    #[derive(Serialize, Deserialize)]
-    struct Results {
-        result: Resources,
+#[cfg(feature = "net")]
+impl From<reqwest::Error> for StimmtError {
+    fn from(err: reqwest::Error) -> Self {
+        StimmtError::Http(err)
     }
+}
 
-    let response = reqwest::get(url).await?.text().await?;
-    let results: Results = serde_json::from_str(&response)?;
-    let resources = results.result.resources;
-    let resource = resources.iter().max_by(|a, b| a.coverage.cmp(&b.coverage));
-    if let Some(resource) = resource {
-        Ok(resource.url.clone())
-    } else {
-        Err(anyhow::Error::msg("no resources found"))
+impl From<serde_json::Error> for StimmtError {
+    fn from(err: serde_json::Error) -> Self {
+        StimmtError::Parse(err)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum Lang {
-    DE,
-    FR,
-    IT,
-    RM,
-    EN,
+impl From<std::io::Error> for StimmtError {
+    fn from(err: std::io::Error) -> Self {
+        StimmtError::Io(err)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct IssueTitle {
-    #[serde(rename = "langKey")]
-    lang: Lang,
-    text: String,
+#[cfg(feature = "csv")]
+impl From<csv::Error> for StimmtError {
+    fn from(err: csv::Error) -> Self {
+        StimmtError::Csv(err)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Outcome {
-    #[serde(rename = "gebietAusgezaehlt")]
-    pub count_completed: bool,
-    #[serde(rename = "jaStimmenAbsolut")]
-    pub yes_votes: u32,
-    #[serde(rename = "neinStimmenAbsolut")]
-    pub no_votes: u32,
-    #[serde(rename = "eingelegteStimmzettel")]
-    pub cast_ballot_papers: u32,
-    #[serde(rename = "anzahlStimmberechtigte")]
-    pub eligible_voters: u32,
+pub type Result<T> = std::result::Result<T, StimmtError>;
+
+/// HTTP client reused across requests, so that connections are pooled instead of
+/// being set up again for every fetch.
+#[cfg(feature = "net")]
+#[derive(Debug, Clone)]
+pub struct Client {
+    client: reqwest::Client,
+    #[cfg(target_arch = "wasm32")]
+    timeout: Option<Duration>,
+    retries: u32,
+    backoff: Duration,
+    #[cfg(not(target_arch = "wasm32"))]
+    rate_limiter: RateLimiter,
 }
 
-impl Outcome {
-    pub fn yes_ratio(&self) -> f64 {
-        1.0 / self.valid_votes() as f64 * self.yes_votes as f64
+/// Configures a [`Client`]. By default requests time out after 30 seconds, are not
+/// retried, are limited by [`RateLimiter::default`] and go through the proxies in
+/// the `HTTP_PROXY` and `HTTPS_PROXY` environment variables.
+///
+/// On `wasm32` the browser's fetch API is used, which has no client-wide timeout,
+/// so the timeout is set on every request instead, and retries are sent without
+/// the backoff delay.
+#[cfg(feature = "net")]
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+    user_agent: Option<String>,
+    headers: header::HeaderMap,
+    #[cfg(not(target_arch = "wasm32"))]
+    rate_limiter: RateLimiter,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    no_proxy: bool,
+}
+
+#[cfg(feature = "net")]
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            retries: 0,
+            backoff: Duration::from_millis(500),
+            user_agent: None,
+            headers: header::HeaderMap::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            rate_limiter: RateLimiter::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            proxy: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            no_proxy: false,
+        }
     }
+}
 
-    pub fn no_ratio(&self) -> f64 {
-        1.0 / self.valid_votes() as f64 * self.no_votes as f64
+#[cfg(feature = "net")]
+impl ClientBuilder {
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
     }
 
-    pub fn valid_votes(&self) -> u32 {
-        self.yes_votes + self.no_votes
+    /// How often a request is retried after a transient error: a timeout, a
+    /// connection failure or a server error (5xx). Parse errors and 404 are never
+    /// retried.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
-    pub fn invalid_votes(&self) -> u32 {
-        self.cast_ballot_papers - self.valid_votes()
+    /// Delay before the first retry, doubling with every further retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
     }
 
-    pub fn valid_votes_ratio(&self) -> f64 {
-        1.0 / self.cast_ballot_papers as f64 * self.valid_votes() as f64
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
     }
 
-    pub fn invalid_votes_ratio(&self) -> f64 {
-        1.0 / self.cast_ballot_papers as f64 * self.invalid_votes() as f64
+    /// Sends the header with every request.
+    pub fn header(mut self, name: header::HeaderName, value: header::HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
     }
 
-    pub fn turnout(&self) -> f64 {
-        1.0 / self.eligible_voters as f64 * self.valid_votes() as f64
+    /// Limits the requests of the client, retries included. Clones of the client
+    /// share the limiter.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
     }
-}
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct District {
-    #[serde(rename = "geoLevelnummer")]
-    pub geo_levelnumber: String,
-    #[serde(rename = "geoLevelname")]
-    pub geo_levelname: String,
-    #[serde(rename = "resultat")]
-    pub outcome: Outcome,
+    /// Sends all requests through the proxy at `url` instead of the ones from the
+    /// environment.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self.no_proxy = false;
+        self
+    }
+
+    /// Ignores the proxies from the environment.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn no_proxy(mut self) -> Self {
+        self.proxy = None;
+        self.no_proxy = true;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let mut builder = reqwest::Client::builder().default_headers(self.headers);
+        if let Some(user_agent) = self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            builder = builder.timeout(self.timeout);
+            if let Some(proxy) = &self.proxy {
+                builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+            } else if self.no_proxy {
+                builder = builder.no_proxy();
+            }
+        }
+        let client = builder.build()?;
+        Ok(Client {
+            client,
+            #[cfg(target_arch = "wasm32")]
+            timeout: Some(self.timeout),
+            retries: self.retries,
+            backoff: self.backoff,
+            #[cfg(not(target_arch = "wasm32"))]
+            rate_limiter: self.rate_limiter,
+        })
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct Commune {
-    #[serde(rename = "geoLevelnummer")]
-    pub geo_levelnumber: String,
-    #[serde(rename = "geoLevelname")]
-    pub geo_levelname: String,
-    #[serde(rename = "geoLevelParentnummer")]
-    pub geo_level_parentnumber: String,
-    #[serde(rename = "resultat")]
-    pub outcome: Outcome,
+#[cfg(feature = "net")]
+fn is_transient(err: &reqwest::Error) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    if err.is_connect() {
+        return true;
+    }
+    err.is_timeout() || err.is_body() || err.status().is_some_and(|status| status.is_server_error())
 }
 
-type Constituency = Commune;
+#[cfg(feature = "net")]
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-pub mod national {
-    use super::*;
+#[cfg(feature = "net")]
+impl Client {
+    /// A client with the default configuration of [`ClientBuilder`].
+    ///
+    /// # Panics
+    ///
+    /// Like [`reqwest::Client::new`], if the TLS backend cannot be initialized.
+    pub fn new() -> Self {
+        Self::builder()
+            .build()
+            .expect("failed to build HTTP client")
+    }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
-    pub struct OutcomeCantons {
-        #[serde(rename = "jaStaendeGanz")]
-        pub yes_full_cantons: u8,
-        #[serde(rename = "neinStaendeGanz")]
-        pub no_full_cantons: u8,
-        #[serde(rename = "anzahlStaendeGanz")]
-        pub full_canton_count: u8,
-        #[serde(rename = "jaStaendeHalb")]
-        pub yes_half_cantons: u8,
-        #[serde(rename = "neinStaendeHalb")]
-        pub no_half_cantons: u8,
-        #[serde(rename = "anzahlStaendeHalb")]
-        pub half_canton_count: u8,
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-    pub struct Canton {
-        #[serde(rename = "geoLevelnummer")]
-        pub geo_levelnumber: String,
-        #[serde(rename = "geoLevelname")]
-        pub geo_levelname: String,
-        #[serde(rename = "resultat")]
-        pub outcome: Outcome,
-        #[serde(rename = "bezirke")]
-        pub districts: Option<Vec<District>>,
-        #[serde(rename = "gemeinden")]
-        pub communes: Option<Vec<Commune>>,
-        #[serde(rename = "zaehlkreise")]
-        pub constituencies: Option<Vec<Constituency>>,
+    /// Uses `client` as is, without retries and with the default rate limit.
+    pub fn from_reqwest(client: reqwest::Client) -> Self {
+        let defaults = ClientBuilder::default();
+        Self {
+            client,
+            #[cfg(target_arch = "wasm32")]
+            timeout: None,
+            retries: 0,
+            backoff: defaults.backoff,
+            #[cfg(not(target_arch = "wasm32"))]
+            rate_limiter: defaults.rate_limiter,
+        }
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-    pub struct Issue {
-        #[serde(rename = "vorlagenId")]
-        pub issue_id: u32,
-        #[serde(rename = "reihenfolgeAnzeige")]
-        pub display_order: u32,
-        #[serde(rename = "vorlagenTitel")]
-        pub issue_title: Vec<IssueTitle>,
-        #[serde(rename = "vorlageBeendet")]
-        pub issue_completed: bool,
-        #[serde(rename = "provisorisch")]
-        pub provisional: bool,
-        #[serde(rename = "vorlageAngenommen")]
-        pub issue_accepted: bool,
-        #[serde(rename = "vorlagenArtId")]
-        pub issue_type_id: u32,
-        #[serde(rename = "hauptvorlagenId")]
-        pub main_issue_id: u32,
-        #[serde(rename = "reserveInfoText")]
-        pub reserve_info_text: Option<String>,
-        #[serde(rename = "doppeltesMehr")]
-        pub double_majority: bool,
-        #[serde(rename = "staende")]
-        pub outcome_cantons: OutcomeCantons,
-        #[serde(rename = "resultat")]
-        pub outcome: Outcome,
-        #[serde(rename = "kantone")]
-        pub cantons: Vec<Canton>,
+    async fn get_text(&self, url: &str) -> Result<String> {
+        let (text, _) = self.get_if_none_match(url, None).await?.unwrap_or_default();
+        Ok(text)
     }
 
-    impl Issue {
-        pub fn get_title(&self, lang: Lang) -> Option<&str> {
-            self.issue_title.iter().find_map(|title| {
-                if title.lang == lang && !title.text.chars().all(char::is_whitespace) {
-                    Some(title.text.as_str())
-                } else {
-                    None
+    /// Fetches `url`, sending `etag` as `If-None-Match`. Returns `None` if the
+    /// server answers `304 Not Modified`, otherwise the body with its ETag.
+    async fn get_if_none_match(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<(String, Option<String>)>> {
+        let mut backoff = self.backoff;
+        let mut retries = self.retries;
+        loop {
+            match self.try_get(url, etag).await {
+                Err(StimmtError::Http(err)) if retries > 0 && is_transient(&err) => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    retries -= 1;
                 }
-            })
+                result => return result,
+            }
         }
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-    pub struct Country {
-        #[serde(rename = "geoLevelnummer")]
-        pub geo_levelnumber: u8,
-        #[serde(rename = "geoLevelname")]
-        pub geo_levelname: String,
-        #[serde(rename = "nochKeineInformation")]
-        pub no_infos_yet: bool,
-        #[serde(rename = "vorlagen")]
-        pub issues: Vec<Issue>,
+    async fn try_get(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<(String, Option<String>)>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.rate_limiter.acquire().await;
+        let mut request = self.client.get(url);
+        #[cfg(target_arch = "wasm32")]
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => return Err(StimmtError::NotFound(url.to_string())),
+            StatusCode::NOT_MODIFIED => return Ok(None),
+            _ => {}
+        }
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        Ok(Some((response.error_for_status()?.text().await?, etag)))
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-    pub struct Data {
-        pub abstimmtag: String,
-        pub timestamp: String,
-        #[serde(rename = "schweiz")]
-        pub country: Country,
+    /// All resources of a CKAN package, e.g. [`national::PACKAGE_ID`].
+    pub async fn list_resources(&self, package_id: &str) -> Result<Vec<ResourceInfo>> {
+        let response = self.get_text(&package_url(package_id)).await?;
+        parse_resources(&response)
     }
 
-    pub async fn get_data_by_url(url: &str) -> Result<Data> {
-        let response = reqwest::get(url).await?.text().await?;
-        let data: Data = serde_json::from_str(&response)?;
-        Ok(data)
+    /// Resolves a CKAN package into the URL of its latest resource.
+    async fn latest_url(&self, package_id: &str) -> Result<String> {
+        latest_resource_url(self.list_resources(package_id).await?)
     }
 
-    pub async fn get_latest() -> Result<Data> {
-        let url = "https://ckan.opendata.swiss/api/3/action/package_show?id=echtzeitdaten-am-abstimmungstag-zu-eidgenoessischen-abstimmungsvorlagen";
-        let url = get_latest_url(url).await?;
-        let response = reqwest::get(url).await?.text().await?;
-        let data: Data = serde_json::from_str(&response)?;
-        Ok(data)
+    /// Fetches the resource of a CKAN package whose coverage is exactly `coverage`,
+    /// as listed by [`Client::list_resources`].
+    pub async fn by_coverage<T: DeserializeOwned>(
+        &self,
+        package_id: &str,
+        coverage: &str,
+    ) -> Result<T> {
+        let url = coverage_resource_url(self.list_resources(package_id).await?, coverage)?;
+        Ok(serde_json::from_str(&self.get_text(&url).await?)?)
     }
-}
 
-pub mod cantonal {
-    use super::*;
+    pub async fn national_by_url(&self, url: &str) -> Result<national::Data> {
+        let response = self.get_text(url).await?;
+        national::Data::from_json(&response)
+    }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-    pub struct Issue {
-        #[serde(rename = "vorlagenId")]
-        pub issue_id: u32,
-        #[serde(rename = "reihenfolgeAnzeige")]
-        pub display_order: u32,
-        #[serde(rename = "vorlagenTitel")]
-        pub issue_title: Vec<IssueTitle>,
-        #[serde(rename = "vorlageBeendet")]
-        pub issue_completed: bool,
-        #[serde(rename = "vorlageAngenommen")]
-        pub issue_accepted: bool,
-        #[serde(rename = "vorlagenArtId")]
-        pub issue_type_id: u32,
-        #[serde(rename = "hauptvorlagenId")]
-        pub main_issue_id: Option<u32>,
-        #[serde(rename = "resultat")]
-        pub outcome: Outcome,
-        #[serde(rename = "bezirke")]
-        pub districts: Option<Vec<District>>,
-        #[serde(rename = "gemeinden")]
-        pub communes: Option<Vec<Commune>>,
-        #[serde(rename = "zaehlkreise")]
-        pub constituencies: Option<Vec<Constituency>>,
+    pub async fn national_by_date(
+        &self,
+        year: i32,
+        month: u32,
+        day: u32,
+    ) -> Result<national::Data> {
+        self.national_by_url(&national::url_for_date(year, month, day))
+            .await
     }
 
-    impl Issue {
-        pub fn get_title(&self, lang: Lang) -> Option<&str> {
-            self.issue_title.iter().find_map(|title| {
-                if title.lang == lang && !title.text.chars().all(char::is_whitespace) {
-                    Some(title.text.as_str())
-                } else {
-                    None
+    pub async fn national_latest(&self) -> Result<national::Data> {
+        let url = self.latest_url(national::PACKAGE_ID).await?;
+        self.national_by_url(&url).await
+    }
+
+    pub async fn cantonal_by_url(&self, url: &str) -> Result<cantonal::Data> {
+        let response = self.get_text(url).await?;
+        cantonal::Data::from_json(&response)
+    }
+
+    pub async fn cantonal_by_date(
+        &self,
+        year: i32,
+        month: u32,
+        day: u32,
+    ) -> Result<cantonal::Data> {
+        self.cantonal_by_url(&cantonal::url_for_date(year, month, day))
+            .await
+    }
+
+    pub async fn cantonal_latest(&self) -> Result<cantonal::Data> {
+        let url = self.latest_url(cantonal::PACKAGE_ID).await?;
+        self.cantonal_by_url(&url).await
+    }
+
+    /// Fetches the latest national and cantonal data concurrently.
+    pub async fn latest_all(&self) -> Result<(national::Data, cantonal::Data)> {
+        futures_util::future::try_join(self.national_latest(), self.cantonal_latest()).await
+    }
+
+    /// Fetches the latest national data every `interval` and yields it whenever its
+    /// `timestamp` changed. Failed fetches are yielded as errors without ending the
+    /// stream. The stream has to be pinned, e.g. with [`std::pin::pin!`], to be
+    /// polled.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn national_stream(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<national::Data>> {
+        poll_changes(
+            self.clone(),
+            interval,
+            |client| async move { client.national_latest().await },
+            |data| &data.timestamp,
+        )
+    }
+
+    /// Like [`Client::national_stream`] for the cantonal data.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn cantonal_stream(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<cantonal::Data>> {
+        poll_changes(
+            self.clone(),
+            interval,
+            |client| async move { client.cantonal_latest().await },
+            |data| &data.timestamp,
+        )
+    }
+}
+
+/// Calls `fetch` every `interval` and yields the results, skipping data with the
+/// same timestamp as the last one yielded.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+fn poll_changes<T, F, Fut>(
+    client: Client,
+    interval: Duration,
+    fetch: F,
+    timestamp: fn(&T) -> &String,
+) -> impl Stream<Item = Result<T>>
+where
+    F: Fn(Client) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let state = (client, ticker, fetch, None::<String>);
+    futures_util::stream::unfold(
+        state,
+        move |(client, mut ticker, fetch, mut last)| async move {
+            loop {
+                ticker.tick().await;
+                match fetch(client.clone()).await {
+                    Ok(data) if last.as_ref() == Some(timestamp(&data)) => continue,
+                    Ok(data) => {
+                        last = Some(timestamp(&data).clone());
+                        return Some((Ok(data), (client, ticker, fetch, last)));
+                    }
+                    Err(err) => return Some((Err(err), (client, ticker, fetch, last))),
                 }
-            })
+            }
+        },
+    )
+}
+
+/// Client that keeps the downloaded JSON on disk and serves it from there while it
+/// is younger than the TTL. Entries are keyed by the resource URL, so for the
+/// latest data only the CKAN lookup is repeated.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+pub struct CachedClient {
+    client: Client,
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl CachedClient {
+    /// # Panics
+    ///
+    /// Like [`Client::new`], if the HTTP client cannot be built.
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            client: Client::new(),
+            dir: dir.into(),
+            ttl,
         }
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-    pub struct Canton {
-        #[serde(rename = "geoLevelnummer")]
-        pub geo_levelnumber: u8,
-        #[serde(rename = "geoLevelname")]
-        pub geo_levelname: String,
-        #[serde(rename = "nochKeineInformation")]
-        pub no_infos_yet: bool,
-        #[serde(rename = "vorlagen")]
-        pub issues: Vec<Issue>,
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
     }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-    pub struct Data {
-        pub abstimmtag: String,
-        pub timestamp: String,
-        #[serde(rename = "kantone")]
-        pub kantone: Vec<Canton>,
+    /// Names the file by the 128-bit FNV-1a hash of the URL, which is stable across
+    /// builds and keeps the file name short however long the URL is.
+    fn cache_path(&self, url: &str) -> PathBuf {
+        let hash = url
+            .bytes()
+            .fold(0x6c62272e07bb014262b821756295c58d, |hash: u128, byte| {
+                (hash ^ byte as u128).wrapping_mul(0x0000000001000000000000000000013b)
+            });
+        self.dir.join(format!("{hash:032x}.json"))
     }
 
-    pub async fn get_data_by_url(url: &str) -> Result<Data> {
-        let response = reqwest::get(url).await?.text().await?;
-        let data: Data = serde_json::from_str(&response)?;
-        Ok(data)
+    async fn get_text(&self, url: &str) -> Result<String> {
+        let path = self.cache_path(url);
+        if let Ok(metadata) = tokio::fs::metadata(&path).await {
+            let age = metadata.modified()?.elapsed().unwrap_or_default();
+            if age < self.ttl {
+                return Ok(tokio::fs::read_to_string(&path).await?);
+            }
+        }
+        self.refresh(url).await
     }
 
-    pub async fn get_latest() -> Result<Data> {
-        let url = "https://ckan.opendata.swiss/api/3/action/package_show?id=echtzeitdaten-am-abstimmungstag-zu-kantonalen-abstimmungsvorlagen";
-        let url = get_latest_url(url).await?;
-        let response = reqwest::get(url).await?.text().await?;
-        let data: Data = serde_json::from_str(&response)?;
-        Ok(data)
+    /// Downloads `url` again regardless of the cache and stores the result.
+    pub async fn refresh(&self, url: &str) -> Result<String> {
+        let response = self.client.get_text(url).await?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.cache_path(url), &response).await?;
+        Ok(response)
+    }
+
+    /// Removes all cached files.
+    pub async fn clear(&self) -> Result<()> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.path().extension().is_some_and(|ext| ext == "json") {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn national_by_url(&self, url: &str) -> Result<national::Data> {
+        national::Data::from_json(&self.get_text(url).await?)
+    }
+
+    pub async fn national_latest(&self) -> Result<national::Data> {
+        let url = self.client.latest_url(national::PACKAGE_ID).await?;
+        self.national_by_url(&url).await
+    }
+
+    pub async fn cantonal_by_url(&self, url: &str) -> Result<cantonal::Data> {
+        cantonal::Data::from_json(&self.get_text(url).await?)
+    }
+
+    pub async fn cantonal_latest(&self) -> Result<cantonal::Data> {
+        let url = self.client.latest_url(cantonal::PACKAGE_ID).await?;
+        self.cantonal_by_url(&url).await
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+const STATIC_URL: &str = "https://ogd-static.voteinfo-app.ch/v1/ogd/sd-t-17-02-";
+
+/// Days since the Unix epoch of the most recent Sunday up to and including `days`.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+fn last_sunday(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday.
+    days - (days + 4).rem_euclid(7)
+}
+
+/// Converts days since the Unix epoch into a (year, month, day) date.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
+
+fn static_url(kind: &str, year: i32, month: u32, day: u32) -> String {
+    format!("{STATIC_URL}{year:04}{month:02}{day:02}-{kind}.json")
+}
+
+/// Guesses the static URL of the most recent voting day by assuming it was the
+/// last Sunday.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+fn guess_static_url(url_for_date: fn(i32, u32, u32) -> String) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days(last_sunday((now.as_secs() / 86400) as i64));
+    url_for_date(year, month, day)
+}
+
+/// All resources of a CKAN package, e.g. [`national::PACKAGE_ID`].
+#[cfg(feature = "net")]
+pub async fn list_resources(package_id: &str) -> Result<Vec<ResourceInfo>> {
+    Client::builder().build()?.list_resources(package_id).await
+}
+
+/// Fetches the latest national and cantonal data concurrently, see
+/// [`Client::latest_all`].
+#[cfg(feature = "net")]
+pub async fn get_latest_all() -> Result<(national::Data, cantonal::Data)> {
+    Client::builder().build()?.latest_all().await
+}
+
+/// Fetches the resource of a CKAN package with the given coverage, e.g.
+/// `get_by_coverage::<national::Data>(national::PACKAGE_ID, "2024-09-22")`.
+#[cfg(feature = "net")]
+pub async fn get_by_coverage<T: DeserializeOwned>(package_id: &str, coverage: &str) -> Result<T> {
+    Client::builder()
+        .build()?
+        .by_coverage(package_id, coverage)
+        .await
+}
+
+/// Blocking client shared by all synchronous fetches, so that the CKAN lookup and
+/// the data download of `get_latest` reuse the same connection pool.
+#[cfg(feature = "blocking")]
+fn blocking_client() -> Result<&'static reqwest::blocking::Client> {
+    static CLIENT: std::sync::OnceLock<reqwest::blocking::Client> = std::sync::OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = reqwest::blocking::Client::builder().build()?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+#[cfg(feature = "blocking")]
+fn blocking_get_text(url: &str) -> Result<String> {
+    let response = blocking_client()?.get(url).send()?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(StimmtError::NotFound(url.to_string()));
+    }
+    Ok(response.error_for_status()?.text()?)
+}
+
+/// A resource of a CKAN package, i.e. one published data file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ResourceInfo {
+    /// The period the data covers, usually the voting day like `2024-09-22`.
+    pub coverage: String,
+    /// Where the JSON data can be downloaded.
+    pub url: String,
+}
+
+#[cfg(feature = "net")]
+fn package_url(package_id: &str) -> String {
+    format!("https://ckan.opendata.swiss/api/3/action/package_show?id={package_id}")
+}
+
+/// Parses the resources out of a CKAN `package_show` response.
+#[cfg(feature = "net")]
+fn parse_resources(response: &str) -> Result<Vec<ResourceInfo>> {
+    #[derive(Serialize, Deserialize)]
+    struct Resources {
+        resources: Vec<ResourceInfo>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Error {
+        #[serde(rename = "__type")]
+        kind: Option<String>,
+        message: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Results {
+        success: Option<bool>,
+        result: Option<Resources>,
+        error: Option<Error>,
+    }
+
+    let results: Results = serde_json::from_str(response)?;
+    if results.success == Some(false) {
+        let message = match results.error {
+            Some(Error {
+                kind: Some(kind),
+                message: Some(message),
+            }) => format!("{kind}: {message}"),
+            Some(Error { kind, message }) => kind.or(message).unwrap_or_default(),
+            None => String::new(),
+        };
+        return Err(StimmtError::Ckan(message));
+    }
+    match results.result {
+        Some(result) => Ok(result.resources),
+        None => Err(StimmtError::Parse(de::Error::missing_field("result"))),
+    }
+}
+
+/// Picks the URL of the resource with the newest coverage.
+#[cfg(feature = "net")]
+fn latest_resource_url(resources: Vec<ResourceInfo>) -> Result<String> {
+    let resource = resources.into_iter().max_by(|a, b| {
+        (parse_coverage(&a.coverage), &a.coverage).cmp(&(parse_coverage(&b.coverage), &b.coverage))
+    });
+    if let Some(resource) = resource {
+        Ok(resource.url)
+    } else {
+        Err(StimmtError::NoResources)
+    }
+}
+
+#[cfg(feature = "net")]
+fn coverage_resource_url(resources: Vec<ResourceInfo>, coverage: &str) -> Result<String> {
+    resources
+        .into_iter()
+        .find(|resource| resource.coverage == coverage)
+        .map(|resource| resource.url)
+        .ok_or_else(|| StimmtError::CoverageNotFound(coverage.to_string()))
+}
+
+/// Parses a coverage date like `2024-09-22`, `2024-9-22`, `22.09.2024` or
+/// `20240922` into a comparable (year, month, day).
+#[cfg(feature = "net")]
+fn parse_coverage(coverage: &str) -> Option<(u32, u32, u32)> {
+    let coverage = coverage.trim();
+    let parts: Vec<&str> = coverage.split(['-', '.', '/']).collect();
+    let (year, month, day) = match parts[..] {
+        [year, month, day] if year.len() == 4 => (year, month, day),
+        [day, month, year] if year.len() == 4 => (year, month, day),
+        [date] if date.len() == 8 => (&date[..4], &date[4..6], &date[6..]),
+        _ => return None,
+    };
+    let date = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    ((1..=12).contains(&date.1) && (1..=31).contains(&date.2)).then_some(date)
+}
+
+#[cfg(feature = "net")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refresh<T> {
+    Refreshed(T),
+    Unchanged,
+}
+
+/// Token bucket limiting how many requests are sent per second, see
+/// [`ClientBuilder::rate_limiter`]. Clones share the same bucket, so one limiter
+/// can throttle several clients running concurrently.
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl RateLimiter {
+    /// Allows up to `requests_per_second` requests per second, with bursts of the
+    /// same size. A rate of zero is treated as one.
+    pub fn new(requests_per_second: u32) -> Self {
+        let rate = requests_per_second.max(1) as f64;
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                rate,
+                tokens: rate,
+                last: Instant::now(),
+            })),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(bucket.rate);
+        bucket.last = now;
+        if bucket.tokens < 1.0 {
+            let wait = (1.0 - bucket.tokens) / bucket.rate;
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            bucket.tokens = 1.0;
+            bucket.last = Instant::now();
+        }
+        bucket.tokens -= 1.0;
+    }
+}
+
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+/// Keeps the last fetched data of a URL together with its ETag, so that polling
+/// only downloads and parses the data again when the server reports a change.
+#[cfg(feature = "net")]
+pub struct CachedFetcher<T> {
+    client: Client,
+    url: String,
+    etag: Option<String>,
+    data: Option<T>,
+}
+
+#[cfg(feature = "net")]
+impl<T: DeserializeOwned + Clone> CachedFetcher<T> {
+    /// # Panics
+    ///
+    /// Like [`Client::new`], if the HTTP client cannot be built.
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.to_string(),
+            etag: None,
+            data: None,
+        }
+    }
+
+    pub fn with_client(mut self, client: &Client) -> Self {
+        self.client = client.clone();
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    pub fn data(&self) -> Option<&T> {
+        self.data.as_ref()
+    }
+
+    /// Fetches the URL again, unless the server reports that the data is unchanged
+    /// since the last refresh. Retries and errors are handled by the [`Client`].
+    pub async fn refresh(&mut self) -> Result<Refresh<T>> {
+        let etag = self.etag.as_deref().filter(|_| self.data.is_some());
+        let Some((response, etag)) = self.client.get_if_none_match(&self.url, etag).await? else {
+            return Ok(Refresh::Unchanged);
+        };
+        let data: T = serde_json::from_str(&response)?;
+        self.etag = etag;
+        Ok(Refresh::Refreshed(self.data.insert(data).clone()))
+    }
+}
+
+/// Ordered as in [`Lang::ALL`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    DE,
+    FR,
+    IT,
+    RM,
+    EN,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 5] = [Lang::DE, Lang::FR, Lang::IT, Lang::RM, Lang::EN];
+
+    pub fn iso_639_1(self) -> &'static str {
+        match self {
+            Lang::DE => "de",
+            Lang::FR => "fr",
+            Lang::IT => "it",
+            Lang::RM => "rm",
+            Lang::EN => "en",
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.iso_639_1())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLangError(String);
+
+impl fmt::Display for ParseLangError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown language: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseLangError {}
+
+/// Parses ISO 639-1 codes, ignoring case.
+impl FromStr for Lang {
+    type Err = ParseLangError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Lang::ALL
+            .into_iter()
+            .find(|lang| lang.iso_639_1().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseLangError(s.to_string()))
+    }
+}
+
+/// The kind of an issue, as encoded in `vorlagenArtId`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IssueType {
+    MandatoryReferendum,
+    OptionalReferendum,
+    PopularInitiative,
+    CounterProposal,
+    /// The tie-breaker between an initiative and its counter-proposal.
+    TieBreaker,
+    Unknown(u32),
+}
+
+impl IssueType {
+    pub fn from_id(id: u32) -> IssueType {
+        match id {
+            1 => IssueType::MandatoryReferendum,
+            2 => IssueType::OptionalReferendum,
+            3 => IssueType::PopularInitiative,
+            4 => IssueType::CounterProposal,
+            5 => IssueType::TieBreaker,
+            id => IssueType::Unknown(id),
+        }
+    }
+
+    pub fn id(self) -> u32 {
+        match self {
+            IssueType::MandatoryReferendum => 1,
+            IssueType::OptionalReferendum => 2,
+            IssueType::PopularInitiative => 3,
+            IssueType::CounterProposal => 4,
+            IssueType::TieBreaker => 5,
+            IssueType::Unknown(id) => id,
+        }
+    }
+}
+
+impl fmt::Display for IssueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueType::MandatoryReferendum => f.write_str("mandatory referendum"),
+            IssueType::OptionalReferendum => f.write_str("optional referendum"),
+            IssueType::PopularInitiative => f.write_str("popular initiative"),
+            IssueType::CounterProposal => f.write_str("counter-proposal"),
+            IssueType::TieBreaker => f.write_str("tie-breaker"),
+            IssueType::Unknown(id) => write!(f, "unknown issue type {id}"),
+        }
+    }
+}
+
+/// The 26 cantons, numbered in their official order as used for `geoLevelnummer`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Canton {
+    ZH = 1,
+    BE,
+    LU,
+    UR,
+    SZ,
+    OW,
+    NW,
+    GL,
+    ZG,
+    FR,
+    SO,
+    BS,
+    BL,
+    SH,
+    AR,
+    AI,
+    SG,
+    GR,
+    AG,
+    TG,
+    TI,
+    VD,
+    VS,
+    NE,
+    GE,
+    JU,
+}
+
+const CANTON_NAMES: [[&str; 5]; 26] = [
+    ["Zürich", "Zurich", "Zurigo", "Turitg", "Zurich"],
+    ["Bern", "Berne", "Berna", "Berna", "Bern"],
+    ["Luzern", "Lucerne", "Lucerna", "Lucerna", "Lucerne"],
+    ["Uri", "Uri", "Uri", "Uri", "Uri"],
+    ["Schwyz", "Schwytz", "Svitto", "Sviz", "Schwyz"],
+    ["Obwalden", "Obwald", "Obvaldo", "Sursilvania", "Obwalden"],
+    [
+        "Nidwalden",
+        "Nidwald",
+        "Nidvaldo",
+        "Sutsilvania",
+        "Nidwalden",
+    ],
+    ["Glarus", "Glaris", "Glarona", "Glaruna", "Glarus"],
+    ["Zug", "Zoug", "Zugo", "Zug", "Zug"],
+    ["Freiburg", "Fribourg", "Friburgo", "Friburg", "Fribourg"],
+    ["Solothurn", "Soleure", "Soletta", "Soloturn", "Solothurn"],
+    [
+        "Basel-Stadt",
+        "Bâle-Ville",
+        "Basilea Città",
+        "Basilea-Citad",
+        "Basel-Stadt",
+    ],
+    [
+        "Basel-Landschaft",
+        "Bâle-Campagne",
+        "Basilea Campagna",
+        "Basilea-Champagna",
+        "Basel-Landschaft",
+    ],
+    [
+        "Schaffhausen",
+        "Schaffhouse",
+        "Sciaffusa",
+        "Schaffusa",
+        "Schaffhausen",
+    ],
+    [
+        "Appenzell Ausserrhoden",
+        "Appenzell Rhodes-Extérieures",
+        "Appenzello Esterno",
+        "Appenzell Dadora",
+        "Appenzell Ausserrhoden",
+    ],
+    [
+        "Appenzell Innerrhoden",
+        "Appenzell Rhodes-Intérieures",
+        "Appenzello Interno",
+        "Appenzell Dadens",
+        "Appenzell Innerrhoden",
+    ],
+    [
+        "St. Gallen",
+        "Saint-Gall",
+        "San Gallo",
+        "Son Gagl",
+        "St. Gallen",
+    ],
+    ["Graubünden", "Grisons", "Grigioni", "Grischun", "Grisons"],
+    ["Aargau", "Argovie", "Argovia", "Argovia", "Aargau"],
+    ["Thurgau", "Thurgovie", "Turgovia", "Turgovia", "Thurgau"],
+    ["Tessin", "Tessin", "Ticino", "Tessin", "Ticino"],
+    ["Waadt", "Vaud", "Vaud", "Vad", "Vaud"],
+    ["Wallis", "Valais", "Vallese", "Vallais", "Valais"],
+    [
+        "Neuenburg",
+        "Neuchâtel",
+        "Neuchâtel",
+        "Neuchâtel",
+        "Neuchâtel",
+    ],
+    ["Genf", "Genève", "Ginevra", "Genevra", "Geneva"],
+    ["Jura", "Jura", "Jura", "Jura", "Jura"],
+];
+
+impl Canton {
+    pub const ALL: [Canton; 26] = [
+        Canton::ZH,
+        Canton::BE,
+        Canton::LU,
+        Canton::UR,
+        Canton::SZ,
+        Canton::OW,
+        Canton::NW,
+        Canton::GL,
+        Canton::ZG,
+        Canton::FR,
+        Canton::SO,
+        Canton::BS,
+        Canton::BL,
+        Canton::SH,
+        Canton::AR,
+        Canton::AI,
+        Canton::SG,
+        Canton::GR,
+        Canton::AG,
+        Canton::TG,
+        Canton::TI,
+        Canton::VD,
+        Canton::VS,
+        Canton::NE,
+        Canton::GE,
+        Canton::JU,
+    ];
+
+    pub fn from_number(number: u8) -> Option<Canton> {
+        Self::ALL.get(usize::from(number).checked_sub(1)?).copied()
+    }
+
+    pub fn from_abbreviation(abbreviation: &str) -> Option<Canton> {
+        Self::ALL
+            .into_iter()
+            .find(|canton| canton.abbreviation().eq_ignore_ascii_case(abbreviation))
+    }
+
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            Canton::ZH => "ZH",
+            Canton::BE => "BE",
+            Canton::LU => "LU",
+            Canton::UR => "UR",
+            Canton::SZ => "SZ",
+            Canton::OW => "OW",
+            Canton::NW => "NW",
+            Canton::GL => "GL",
+            Canton::ZG => "ZG",
+            Canton::FR => "FR",
+            Canton::SO => "SO",
+            Canton::BS => "BS",
+            Canton::BL => "BL",
+            Canton::SH => "SH",
+            Canton::AR => "AR",
+            Canton::AI => "AI",
+            Canton::SG => "SG",
+            Canton::GR => "GR",
+            Canton::AG => "AG",
+            Canton::TG => "TG",
+            Canton::TI => "TI",
+            Canton::VD => "VD",
+            Canton::VS => "VS",
+            Canton::NE => "NE",
+            Canton::GE => "GE",
+            Canton::JU => "JU",
+        }
+    }
+
+    pub fn name(self, lang: Lang) -> &'static str {
+        CANTON_NAMES[self as usize - 1][lang as usize]
+    }
+
+    /// Whether the canton only has half a vote in the canton majority.
+    pub fn is_half_canton(self) -> bool {
+        matches!(
+            self,
+            Canton::OW | Canton::NW | Canton::BS | Canton::BL | Canton::AR | Canton::AI
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IssueTitle {
+    #[serde(rename = "langKey")]
+    lang: Lang,
+    text: String,
+}
+
+/// Whether a non-empty title in `lang`, or in any language if `lang` is `None`,
+/// contains `query` ignoring case.
+fn titles_contain(titles: &[IssueTitle], query: &str, lang: Option<Lang>) -> bool {
+    let query = query.to_lowercase();
+    titles.iter().any(|title| {
+        lang.is_none_or(|lang| title.lang == lang)
+            && !title.text.chars().all(char::is_whitespace)
+            && title.text.to_lowercase().contains(&query)
+    })
+}
+
+/// Titles of an issue indexed by language, for repeated lookups.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TitleIndex<'a> {
+    titles: [Option<&'a str>; 5],
+}
+
+impl<'a> TitleIndex<'a> {
+    fn new(issue_title: &'a [IssueTitle]) -> Self {
+        let mut titles = [None; 5];
+        for title in issue_title {
+            let slot = &mut titles[title.lang as usize];
+            if slot.is_none() && !title.text.chars().all(char::is_whitespace) {
+                *slot = Some(title.text.as_str());
+            }
+        }
+        Self { titles }
+    }
+
+    /// Same as `get_title` on the issue the index was built from.
+    pub fn get(&self, lang: Lang) -> Option<&'a str> {
+        self.titles[lang as usize]
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Outcome {
+    #[serde(rename = "gebietAusgezaehlt")]
+    pub count_completed: bool,
+    #[serde(rename = "jaStimmenAbsolut")]
+    pub yes_votes: u32,
+    #[serde(rename = "neinStimmenAbsolut")]
+    pub no_votes: u32,
+    #[serde(rename = "eingelegteStimmzettel")]
+    pub cast_ballot_papers: u32,
+    #[serde(rename = "anzahlStimmberechtigte")]
+    pub eligible_voters: u32,
+}
+
+/// Difference between two outcomes, field by field.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutcomeDelta {
+    pub yes_votes: i64,
+    pub no_votes: i64,
+    pub cast_ballot_papers: i64,
+    pub eligible_voters: i64,
+}
+
+impl OutcomeDelta {
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Adds up the counts, the result is only completed if both outcomes are.
+impl Add for Outcome {
+    type Output = Outcome;
+
+    fn add(self, other: Outcome) -> Outcome {
+        Outcome {
+            count_completed: self.count_completed && other.count_completed,
+            yes_votes: self.yes_votes + other.yes_votes,
+            no_votes: self.no_votes + other.no_votes,
+            cast_ballot_papers: self.cast_ballot_papers + other.cast_ballot_papers,
+            eligible_voters: self.eligible_voters + other.eligible_voters,
+        }
+    }
+}
+
+/// The sum of no outcomes has zero votes and counts as completed.
+impl Sum for Outcome {
+    fn sum<I: Iterator<Item = Outcome>>(iter: I) -> Outcome {
+        iter.fold(
+            Outcome {
+                count_completed: true,
+                yes_votes: 0,
+                no_votes: 0,
+                cast_ballot_papers: 0,
+                eligible_voters: 0,
+            },
+            Add::add,
+        )
+    }
+}
+
+impl<'a> Sum<&'a Outcome> for Outcome {
+    fn sum<I: Iterator<Item = &'a Outcome>>(iter: I) -> Outcome {
+        iter.copied().sum()
+    }
+}
+
+/// Accepts the vote counts either directly inside `resultat` or nested one level
+/// deeper inside a `zahlen` object, as some exports do.
+impl<'de> Deserialize<'de> for Outcome {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Counts {
+            #[serde(rename = "gebietAusgezaehlt")]
+            count_completed: Option<bool>,
+            #[serde(rename = "jaStimmenAbsolut")]
+            yes_votes: Option<u32>,
+            #[serde(rename = "neinStimmenAbsolut")]
+            no_votes: Option<u32>,
+            #[serde(rename = "eingelegteStimmzettel")]
+            cast_ballot_papers: Option<u32>,
+            #[serde(rename = "anzahlStimmberechtigte")]
+            eligible_voters: Option<u32>,
+        }
+
+        #[derive(Deserialize)]
+        struct Layout {
+            #[serde(flatten)]
+            flat: Counts,
+            zahlen: Option<Counts>,
+        }
+
+        let Layout { flat, zahlen } = Layout::deserialize(deserializer)?;
+        let nested = zahlen.as_ref();
+        Ok(Outcome {
+            count_completed: flat
+                .count_completed
+                .or(nested.and_then(|n| n.count_completed))
+                .ok_or_else(|| de::Error::missing_field("gebietAusgezaehlt"))?,
+            yes_votes: flat
+                .yes_votes
+                .or(nested.and_then(|n| n.yes_votes))
+                .ok_or_else(|| de::Error::missing_field("jaStimmenAbsolut"))?,
+            no_votes: flat
+                .no_votes
+                .or(nested.and_then(|n| n.no_votes))
+                .ok_or_else(|| de::Error::missing_field("neinStimmenAbsolut"))?,
+            cast_ballot_papers: flat
+                .cast_ballot_papers
+                .or(nested.and_then(|n| n.cast_ballot_papers))
+                .ok_or_else(|| de::Error::missing_field("eingelegteStimmzettel"))?,
+            eligible_voters: flat
+                .eligible_voters
+                .or(nested.and_then(|n| n.eligible_voters))
+                .ok_or_else(|| de::Error::missing_field("anzahlStimmberechtigte"))?,
+        })
+    }
+}
+
+/// Builds an [`Outcome`] from some of its counts. Missing vote counts are zero,
+/// missing cast ballots are the valid votes, the missing electorate equals the cast
+/// ballots, and counting is not completed unless set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutcomeBuilder {
+    yes_votes: u32,
+    no_votes: u32,
+    cast_ballot_papers: Option<u32>,
+    eligible_voters: Option<u32>,
+    count_completed: bool,
+}
+
+impl OutcomeBuilder {
+    pub fn yes_votes(mut self, yes_votes: u32) -> Self {
+        self.yes_votes = yes_votes;
+        self
+    }
+
+    pub fn no_votes(mut self, no_votes: u32) -> Self {
+        self.no_votes = no_votes;
+        self
+    }
+
+    pub fn cast_ballot_papers(mut self, cast_ballot_papers: u32) -> Self {
+        self.cast_ballot_papers = Some(cast_ballot_papers);
+        self
+    }
+
+    pub fn eligible_voters(mut self, eligible_voters: u32) -> Self {
+        self.eligible_voters = Some(eligible_voters);
+        self
+    }
+
+    pub fn count_completed(mut self, count_completed: bool) -> Self {
+        self.count_completed = count_completed;
+        self
+    }
+
+    pub fn build(self) -> Outcome {
+        let cast_ballot_papers = self
+            .cast_ballot_papers
+            .unwrap_or(self.yes_votes + self.no_votes);
+        Outcome::new(
+            self.yes_votes,
+            self.no_votes,
+            cast_ballot_papers,
+            self.eligible_voters.unwrap_or(cast_ballot_papers),
+            self.count_completed,
+        )
+    }
+}
+
+/// `part / whole`, or `None` if `whole` is zero.
+fn ratio(part: u32, whole: u32) -> Option<f64> {
+    (whole != 0).then(|| part as f64 / whole as f64)
+}
+
+/// The ratio methods return `None` when their denominator is zero, e.g. before a
+/// geo level has reported any votes.
+impl Outcome {
+    /// Debug builds assert that the valid votes do not exceed the cast ballots and
+    /// those do not exceed the electorate.
+    pub fn new(
+        yes_votes: u32,
+        no_votes: u32,
+        cast_ballot_papers: u32,
+        eligible_voters: u32,
+        count_completed: bool,
+    ) -> Self {
+        debug_assert!(yes_votes + no_votes <= cast_ballot_papers);
+        debug_assert!(cast_ballot_papers <= eligible_voters);
+        Self {
+            count_completed,
+            yes_votes,
+            no_votes,
+            cast_ballot_papers,
+            eligible_voters,
+        }
+    }
+
+    pub fn builder() -> OutcomeBuilder {
+        OutcomeBuilder::default()
+    }
+
+    pub fn yes_ratio(&self) -> Option<f64> {
+        ratio(self.yes_votes, self.valid_votes())
+    }
+
+    pub fn no_ratio(&self) -> Option<f64> {
+        ratio(self.no_votes, self.valid_votes())
+    }
+
+    pub fn valid_votes(&self) -> u32 {
+        self.yes_votes + self.no_votes
+    }
+
+    /// Saturates at zero when there are more valid votes than cast ballots, see
+    /// [`Outcome::is_consistent`].
+    pub fn invalid_votes(&self) -> u32 {
+        self.cast_ballot_papers.saturating_sub(self.valid_votes())
+    }
+
+    /// Whether there are no more valid votes than cast ballots and eligible voters.
+    pub fn is_consistent(&self) -> bool {
+        self.valid_votes() <= self.cast_ballot_papers && self.valid_votes() <= self.eligible_voters
+    }
+
+    pub fn valid_votes_ratio(&self) -> Option<f64> {
+        ratio(self.valid_votes(), self.cast_ballot_papers)
+    }
+
+    pub fn invalid_votes_ratio(&self) -> Option<f64> {
+        ratio(self.invalid_votes(), self.cast_ballot_papers)
+    }
+
+    pub fn turnout(&self) -> Option<f64> {
+        ratio(self.valid_votes(), self.eligible_voters)
+    }
+
+    pub fn abstentions(&self) -> u32 {
+        self.eligible_voters.saturating_sub(self.cast_ballot_papers)
+    }
+
+    pub fn abstention_ratio(&self) -> Option<f64> {
+        ratio(self.abstentions(), self.eligible_voters)
+    }
+
+    /// The counts of `self` minus those of `other`.
+    pub fn delta(&self, other: &Outcome) -> OutcomeDelta {
+        OutcomeDelta {
+            yes_votes: self.yes_votes as i64 - other.yes_votes as i64,
+            no_votes: self.no_votes as i64 - other.no_votes as i64,
+            cast_ballot_papers: self.cast_ballot_papers as i64 - other.cast_ballot_papers as i64,
+            eligible_voters: self.eligible_voters as i64 - other.eligible_voters as i64,
+        }
+    }
+
+    /// Yes votes minus no votes, negative when the no side leads.
+    pub fn margin(&self) -> i64 {
+        self.yes_votes as i64 - self.no_votes as i64
+    }
+
+    /// [`Outcome::margin`] as a share of the valid votes.
+    pub fn margin_ratio(&self) -> Option<f64> {
+        let valid_votes = self.valid_votes();
+        (valid_votes != 0).then(|| self.margin() as f64 / valid_votes as f64)
+    }
+
+    /// Whether the yes and no sides are at most `threshold` of the valid votes
+    /// apart. An outcome without valid votes is never close.
+    pub fn is_close(&self, threshold: f64) -> bool {
+        self.margin_ratio()
+            .is_some_and(|margin| margin.abs() <= threshold)
+    }
+
+    pub fn margin_over_electorate(&self) -> Option<f64> {
+        (self.eligible_voters != 0).then(|| self.margin() as f64 / self.eligible_voters as f64)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct District {
+    #[serde(rename = "geoLevelnummer")]
+    pub geo_levelnumber: String,
+    #[serde(rename = "geoLevelname")]
+    pub geo_levelname: String,
+    #[serde(rename = "resultat")]
+    pub outcome: Outcome,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Commune {
+    #[serde(rename = "geoLevelnummer")]
+    pub geo_levelnumber: String,
+    #[serde(rename = "geoLevelname")]
+    pub geo_levelname: String,
+    #[serde(rename = "geoLevelParentnummer")]
+    pub geo_level_parentnumber: String,
+    #[serde(rename = "resultat")]
+    pub outcome: Outcome,
+}
+
+/// A counting circle (Zählkreis), a subdivision of a large commune such as
+/// Zürich or Winterthur. `geo_level_parentnumber` is the number of that commune.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Constituency {
+    #[serde(rename = "geoLevelnummer")]
+    pub geo_levelnumber: String,
+    #[serde(rename = "geoLevelname")]
+    pub geo_levelname: String,
+    #[serde(rename = "geoLevelParentnummer")]
+    pub geo_level_parentnumber: String,
+    #[serde(rename = "resultat")]
+    pub outcome: Outcome,
+}
+
+impl From<Commune> for Constituency {
+    fn from(commune: Commune) -> Self {
+        Self {
+            geo_levelnumber: commune.geo_levelnumber,
+            geo_levelname: commune.geo_levelname,
+            geo_level_parentnumber: commune.geo_level_parentnumber,
+            outcome: commune.outcome,
+        }
+    }
+}
+
+/// The result of a commune prepared for joining with map data, e.g. as the
+/// properties of a GeoJSON feature.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommuneResult {
+    pub name: String,
+    pub count_completed: bool,
+    pub yes_ratio: Option<f64>,
+    pub turnout: Option<f64>,
+    pub margin_ratio: Option<f64>,
+}
+
+impl From<&Commune> for CommuneResult {
+    fn from(commune: &Commune) -> Self {
+        Self {
+            name: commune.geo_levelname.clone(),
+            count_completed: commune.outcome.count_completed,
+            yes_ratio: commune.outcome.yes_ratio(),
+            turnout: commune.outcome.turnout(),
+            margin_ratio: commune.outcome.margin_ratio(),
+        }
+    }
+}
+
+/// Keys the communes by their BFS number. Non-numeric numbers are skipped if
+/// `skip_invalid` is set and are an error otherwise.
+fn commune_results<'a>(
+    communes: impl Iterator<Item = &'a Commune>,
+    skip_invalid: bool,
+) -> Result<HashMap<u32, CommuneResult>> {
+    let mut results = HashMap::new();
+    for commune in communes {
+        match commune.geo_levelnumber.parse() {
+            Ok(number) => {
+                results.insert(number, CommuneResult::from(commune));
+            }
+            Err(_) if skip_invalid => {}
+            Err(_) => {
+                return Err(StimmtError::InvalidGeoLevel(
+                    commune.geo_levelnumber.clone(),
+                ))
+            }
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(feature = "csv")]
+fn csv_header(lang: Lang) -> [&'static str; 11] {
+    match lang {
+        Lang::DE => [
+            "Kanton",
+            "Gemeindenummer",
+            "Gemeinde",
+            "Ja",
+            "Nein",
+            "Gültig",
+            "Ungültig",
+            "Stimmberechtigte",
+            "Beteiligung",
+            "Ja-Anteil",
+            "Ausgezählt",
+        ],
+        Lang::FR => [
+            "Canton",
+            "Numéro de commune",
+            "Commune",
+            "Oui",
+            "Non",
+            "Valables",
+            "Nuls",
+            "Électeurs inscrits",
+            "Participation",
+            "Part de oui",
+            "Dépouillé",
+        ],
+        Lang::IT => [
+            "Cantone",
+            "Numero del comune",
+            "Comune",
+            "Sì",
+            "No",
+            "Validi",
+            "Nulli",
+            "Aventi diritto di voto",
+            "Partecipazione",
+            "Quota di sì",
+            "Scrutinato",
+        ],
+        Lang::RM => [
+            "Chantun",
+            "Numer da la vischnanca",
+            "Vischnanca",
+            "Gea",
+            "Na",
+            "Valaivels",
+            "Nunvalaivels",
+            "Persunas cun dretg da votar",
+            "Participaziun",
+            "Quota da gea",
+            "Dumbrà",
+        ],
+        Lang::EN => [
+            "Canton",
+            "Commune number",
+            "Commune",
+            "Yes",
+            "No",
+            "Valid",
+            "Invalid",
+            "Eligible voters",
+            "Turnout",
+            "Yes ratio",
+            "Completed",
+        ],
+    }
+}
+
+/// Writes a header in `lang` and one row per commune, flushing `writer` after every
+/// row so that nothing but the current row is buffered.
+#[cfg(feature = "csv")]
+fn write_communes_csv<'a>(
+    communes: impl Iterator<Item = (&'a str, &'a Commune)>,
+    lang: Lang,
+    writer: impl Write,
+) -> Result<()> {
+    fn optional(ratio: Option<f64>) -> String {
+        ratio.map(|ratio| ratio.to_string()).unwrap_or_default()
+    }
+
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(csv_header(lang))?;
+    writer.flush()?;
+    for (canton, commune) in communes {
+        let outcome = &commune.outcome;
+        writer.write_record([
+            canton.to_string(),
+            commune.geo_levelnumber.clone(),
+            commune.geo_levelname.clone(),
+            outcome.yes_votes.to_string(),
+            outcome.no_votes.to_string(),
+            outcome.valid_votes().to_string(),
+            outcome.invalid_votes().to_string(),
+            outcome.eligible_voters.to_string(),
+            optional(outcome.turnout()),
+            optional(outcome.yes_ratio()),
+            outcome.count_completed.to_string(),
+        ])?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// A geo level with its outcome and the geo levels contained in it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GeoNode {
+    pub geo_levelnumber: String,
+    pub geo_levelname: String,
+    pub outcome: Outcome,
+    pub children: Vec<GeoNode>,
+}
+
+impl GeoNode {
+    fn leaf(geo_levelnumber: &str, geo_levelname: &str, outcome: Outcome) -> Self {
+        Self {
+            geo_levelnumber: geo_levelnumber.to_string(),
+            geo_levelname: geo_levelname.to_string(),
+            outcome,
+            children: Vec::new(),
+        }
+    }
+
+    /// Builds the tree below a canton from its flat geo level lists, attaching
+    /// communes to their district and constituencies to their commune via
+    /// `geo_level_parentnumber`. Children without a matching parent are attached
+    /// to the root.
+    fn tree(
+        mut root: GeoNode,
+        districts: Option<&[District]>,
+        communes: Option<&[Commune]>,
+        constituencies: Option<&[Constituency]>,
+    ) -> Self {
+        let mut constituencies: Vec<_> = constituencies.unwrap_or_default().iter().collect();
+        let mut communes: Vec<_> = communes
+            .unwrap_or_default()
+            .iter()
+            .map(|commune| {
+                let (children, rest) = constituencies
+                    .iter()
+                    .partition(|c| c.geo_level_parentnumber == commune.geo_levelnumber);
+                constituencies = rest;
+                let mut node = GeoNode::from(commune);
+                node.children = children.into_iter().map(GeoNode::from).collect();
+                (node, commune)
+            })
+            .collect();
+        for district in districts.unwrap_or_default() {
+            let (children, rest) = communes
+                .into_iter()
+                .partition(|(_, c)| c.geo_level_parentnumber == district.geo_levelnumber);
+            communes = rest;
+            let mut node = GeoNode::leaf(
+                &district.geo_levelnumber,
+                &district.geo_levelname,
+                district.outcome,
+            );
+            node.children = children.into_iter().map(|(node, _)| node).collect();
+            root.children.push(node);
+        }
+        root.children
+            .extend(communes.into_iter().map(|(node, _)| node));
+        root.children
+            .extend(constituencies.into_iter().map(GeoNode::from));
+        root
+    }
+}
+
+impl From<&Commune> for GeoNode {
+    fn from(commune: &Commune) -> Self {
+        GeoNode::leaf(
+            &commune.geo_levelnumber,
+            &commune.geo_levelname,
+            commune.outcome,
+        )
+    }
+}
+
+impl From<&Constituency> for GeoNode {
+    fn from(constituency: &Constituency) -> Self {
+        GeoNode::leaf(
+            &constituency.geo_levelnumber,
+            &constituency.geo_levelname,
+            constituency.outcome,
+        )
+    }
+}
+
+/// The finest breakdown of a canton or cantonal issue that is populated.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeoLevelKind {
+    /// Only the overall outcome is available.
+    None,
+    Districts,
+    Communes,
+    Constituencies,
+}
+
+impl GeoLevelKind {
+    fn of(districts: bool, communes: bool, constituencies: bool) -> Self {
+        if constituencies {
+            GeoLevelKind::Constituencies
+        } else if communes {
+            GeoLevelKind::Communes
+        } else if districts {
+            GeoLevelKind::Districts
+        } else {
+            GeoLevelKind::None
+        }
+    }
+}
+
+/// Deserializes an empty array like a missing or `null` field, as `None`.
+fn non_empty<'de, D, T>(deserializer: D) -> std::result::Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let list = Option::<Vec<T>>::deserialize(deserializer)?;
+    Ok(list.filter(|list| !list.is_empty()))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeoLevel {
+    Country,
+    Canton,
+    District,
+    Commune,
+    Constituency,
+}
+
+/// How far the results of a snapshot have come in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatusSummary {
+    pub completed_issues: usize,
+    pub pending_issues: usize,
+    pub any_provisional: bool,
+    pub no_infos_yet: bool,
+}
+
+impl StatusSummary {
+    /// Whether results are in, all issues are completed and none is provisional.
+    pub fn is_final(&self) -> bool {
+        self.pending_issues == 0 && !self.any_provisional && !self.no_infos_yet
+    }
+}
+
+/// A geo level whose outcome differs between two snapshots.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OutcomeChange {
+    pub level: GeoLevel,
+    pub geo_levelnumber: String,
+    pub geo_levelname: String,
+    /// `None` if the geo level is new.
+    pub previous: Option<Outcome>,
+    pub current: Outcome,
+}
+
+impl OutcomeChange {
+    /// Whether the geo level finished counting since the previous snapshot.
+    pub fn completed(&self) -> bool {
+        self.current.count_completed && !self.previous.is_some_and(|p| p.count_completed)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IssueDiff {
+    pub issue_id: u32,
+    pub changes: Vec<OutcomeChange>,
+}
+
+/// What changed between two snapshots of the same data. Issues and geo levels
+/// are matched by their ids and numbers, so their order does not matter.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataDiff {
+    pub added_issues: Vec<u32>,
+    pub removed_issues: Vec<u32>,
+    /// Issues present in both snapshots with at least one changed outcome.
+    pub changed_issues: Vec<IssueDiff>,
+}
+
+impl DataDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Outcome of a geo level, as compared by [`DataDiff`].
+type GeoEntry<'a> = (GeoLevel, Cow<'a, str>, &'a str, Outcome);
+
+fn geo_entries<'a>(
+    districts: &'a Option<Vec<District>>,
+    communes: &'a Option<Vec<Commune>>,
+    constituencies: &'a Option<Vec<Constituency>>,
+) -> impl Iterator<Item = GeoEntry<'a>> {
+    let districts = districts.iter().flatten().map(|d| {
+        let number = Cow::from(d.geo_levelnumber.as_str());
+        (
+            GeoLevel::District,
+            number,
+            d.geo_levelname.as_str(),
+            d.outcome,
+        )
+    });
+    let communes = communes.iter().flatten().map(|c| {
+        let number = Cow::from(c.geo_levelnumber.as_str());
+        (
+            GeoLevel::Commune,
+            number,
+            c.geo_levelname.as_str(),
+            c.outcome,
+        )
+    });
+    let constituencies = constituencies.iter().flatten().map(|c| {
+        let number = Cow::from(c.geo_levelnumber.as_str());
+        (
+            GeoLevel::Constituency,
+            number,
+            c.geo_levelname.as_str(),
+            c.outcome,
+        )
+    });
+    districts.chain(communes).chain(constituencies)
+}
+
+fn diff_outcomes(current: Vec<GeoEntry<'_>>, previous: Vec<GeoEntry<'_>>) -> Vec<OutcomeChange> {
+    let previous: HashMap<_, _> = previous
+        .into_iter()
+        .map(|(level, number, _, outcome)| ((level, number), outcome))
+        .collect();
+    current
+        .into_iter()
+        .filter_map(|(level, number, name, outcome)| {
+            let previous = previous.get(&(level, Cow::Borrowed(&*number))).copied();
+            (previous != Some(outcome)).then(|| OutcomeChange {
+                level,
+                geo_levelnumber: number.into_owned(),
+                geo_levelname: name.to_string(),
+                previous,
+                current: outcome,
+            })
+        })
+        .collect()
+}
+
+/// Diffs issues given as (issue id, issue) pairs.
+fn diff_issues<'a, I: Copy + 'a>(
+    current: Vec<(u32, I)>,
+    previous: Vec<(u32, I)>,
+    entries: impl Fn(I) -> Vec<GeoEntry<'a>>,
+) -> DataDiff {
+    let mut diff = DataDiff::default();
+    for &(id, issue) in &current {
+        match previous.iter().find(|(previous_id, _)| *previous_id == id) {
+            Some(&(_, previous)) => {
+                let changes = diff_outcomes(entries(issue), entries(previous));
+                if !changes.is_empty() {
+                    diff.changed_issues.push(IssueDiff {
+                        issue_id: id,
+                        changes,
+                    });
+                }
+            }
+            None => diff.added_issues.push(id),
+        }
+    }
+    diff.removed_issues = previous
+        .iter()
+        .filter(|(id, _)| !current.iter().any(|(current_id, _)| current_id == id))
+        .map(|&(id, _)| id)
+        .collect();
+    diff
+}
+
+pub mod national {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct OutcomeCantons {
+        #[serde(rename = "jaStaendeGanz")]
+        pub yes_full_cantons: u8,
+        #[serde(rename = "neinStaendeGanz")]
+        pub no_full_cantons: u8,
+        #[serde(rename = "anzahlStaendeGanz")]
+        pub full_canton_count: u8,
+        #[serde(rename = "jaStaendeHalb")]
+        pub yes_half_cantons: u8,
+        #[serde(rename = "neinStaendeHalb")]
+        pub no_half_cantons: u8,
+        #[serde(rename = "anzahlStaendeHalb")]
+        pub half_canton_count: u8,
+    }
+
+    /// The canton majority (Ständemehr), where each of the six half cantons counts
+    /// half. It is reached with more than half of the total canton weight, a tie
+    /// rejects.
+    impl OutcomeCantons {
+        pub fn yes_canton_weight(&self) -> f64 {
+            self.yes_full_cantons as f64 + self.yes_half_cantons as f64 / 2.0
+        }
+
+        pub fn no_canton_weight(&self) -> f64 {
+            self.no_full_cantons as f64 + self.no_half_cantons as f64 / 2.0
+        }
+
+        pub fn total_canton_weight(&self) -> f64 {
+            self.full_canton_count as f64 + self.half_canton_count as f64 / 2.0
+        }
+
+        pub fn cantonal_majority_reached(&self) -> bool {
+            self.yes_canton_weight() > self.total_canton_weight() / 2.0
+        }
+
+        /// Whether enough cantons have reported that the remaining ones can no
+        /// longer change the canton majority.
+        pub fn cantonal_majority_decided(&self) -> bool {
+            let half = self.total_canton_weight() / 2.0;
+            self.yes_canton_weight() > half || self.no_canton_weight() >= half
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Canton {
+        #[serde(rename = "geoLevelnummer")]
+        pub geo_levelnumber: String,
+        #[serde(rename = "geoLevelname")]
+        pub geo_levelname: String,
+        #[serde(rename = "resultat")]
+        pub outcome: Outcome,
+        #[serde(rename = "bezirke", default, deserialize_with = "non_empty")]
+        pub districts: Option<Vec<District>>,
+        #[serde(rename = "gemeinden", default, deserialize_with = "non_empty")]
+        pub communes: Option<Vec<Commune>>,
+        #[serde(rename = "zaehlkreise", default, deserialize_with = "non_empty")]
+        pub constituencies: Option<Vec<Constituency>>,
+    }
+
+    impl Canton {
+        pub fn canton(&self) -> Option<super::Canton> {
+            super::Canton::from_number(self.geo_levelnumber.parse().ok()?)
+        }
+
+        /// Weight of the canton in the canton majority: 0.5 for the six half cantons,
+        /// 1.0 for all others.
+        pub fn canton_weight(&self) -> f64 {
+            match self.canton() {
+                Some(canton) if canton.is_half_canton() => 0.5,
+                _ => 1.0,
+            }
+        }
+
+        /// Sum of the communes of the canton, or of its districts if there are no
+        /// communes. Constituencies only subdivide some communes and are not used.
+        pub fn sub_level_outcome(&self) -> Option<Outcome> {
+            if let Some(communes) = &self.communes {
+                Some(communes.iter().map(|commune| commune.outcome).sum())
+            } else {
+                let districts = self.districts.as_ref()?;
+                Some(districts.iter().map(|district| district.outcome).sum())
+            }
+        }
+
+        /// Compares the outcome of the canton with [`Canton::sub_level_outcome`].
+        pub fn reconcile(&self) -> Reconciliation {
+            let Some(sum) = self.sub_level_outcome() else {
+                return Reconciliation::NoSubLevels;
+            };
+            let fields = [
+                (
+                    OutcomeField::YesVotes,
+                    self.outcome.yes_votes,
+                    sum.yes_votes,
+                ),
+                (OutcomeField::NoVotes, self.outcome.no_votes, sum.no_votes),
+                (
+                    OutcomeField::CastBallotPapers,
+                    self.outcome.cast_ballot_papers,
+                    sum.cast_ballot_papers,
+                ),
+                (
+                    OutcomeField::EligibleVoters,
+                    self.outcome.eligible_voters,
+                    sum.eligible_voters,
+                ),
+            ];
+            fields
+                .into_iter()
+                .find(|(_, expected, actual)| expected != actual)
+                .map_or(Reconciliation::Consistent, |(field, expected, actual)| {
+                    Reconciliation::Mismatch {
+                        field,
+                        expected,
+                        actual,
+                    }
+                })
+        }
+
+        pub fn geo_levels(&self) -> GeoLevelKind {
+            GeoLevelKind::of(
+                self.districts.is_some(),
+                self.communes.is_some(),
+                self.constituencies.is_some(),
+            )
+        }
+
+        pub fn as_tree(&self) -> GeoNode {
+            GeoNode::tree(
+                GeoNode::leaf(&self.geo_levelnumber, &self.geo_levelname, self.outcome),
+                self.districts.as_deref(),
+                self.communes.as_deref(),
+                self.constituencies.as_deref(),
+            )
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum OutcomeField {
+        YesVotes,
+        NoVotes,
+        CastBallotPapers,
+        EligibleVoters,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Reconciliation {
+        Consistent,
+        /// The first field in which the outcome of the canton (`expected`) differs
+        /// from the sum of its communes or districts (`actual`).
+        Mismatch {
+            field: OutcomeField,
+            expected: u32,
+            actual: u32,
+        },
+        /// The canton lists neither communes nor districts.
+        NoSubLevels,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Issue {
+        #[serde(rename = "vorlagenId")]
+        pub issue_id: u32,
+        #[serde(rename = "reihenfolgeAnzeige")]
+        pub display_order: u32,
+        #[serde(rename = "vorlagenTitel")]
+        pub issue_title: Vec<IssueTitle>,
+        #[serde(rename = "vorlageBeendet")]
+        pub issue_completed: bool,
+        #[serde(rename = "provisorisch")]
+        pub provisional: bool,
+        #[serde(rename = "vorlageAngenommen")]
+        pub issue_accepted: bool,
+        #[serde(rename = "vorlagenArtId")]
+        pub issue_type_id: u32,
+        #[serde(rename = "hauptvorlagenId")]
+        pub main_issue_id: u32,
+        #[serde(rename = "reserveInfoText")]
+        pub reserve_info_text: Option<String>,
+        #[serde(rename = "doppeltesMehr")]
+        pub double_majority: bool,
+        #[serde(rename = "staende")]
+        pub outcome_cantons: OutcomeCantons,
+        #[serde(rename = "resultat")]
+        pub outcome: Outcome,
+        #[serde(rename = "kantone")]
+        pub cantons: Vec<Canton>,
+    }
+
+    impl Issue {
+        pub fn issue_type(&self) -> IssueType {
+            IssueType::from_id(self.issue_type_id)
+        }
+
+        /// Whether acceptance also requires the majority of the cantons.
+        pub fn requires_canton_majority(&self) -> bool {
+            self.double_majority
+        }
+
+        /// Whether the issue passes with the votes counted so far: a majority of the
+        /// voters and, if required, of the cantons (half cantons counting half).
+        pub fn passes(&self) -> bool {
+            if self.outcome.yes_votes <= self.outcome.no_votes {
+                return false;
+            }
+            if !self.requires_canton_majority() {
+                return true;
+            }
+            self.outcome_cantons.cantonal_majority_reached()
+        }
+
+        /// The trimmed `reserve_info_text`, e.g. about delayed counting or a recount.
+        /// `None` if it is missing or blank.
+        pub fn annotation(&self) -> Option<&str> {
+            let text = self.reserve_info_text.as_deref()?.trim();
+            (!text.is_empty()).then_some(text)
+        }
+
+        pub fn has_annotation(&self) -> bool {
+            self.annotation().is_some()
+        }
+
+        /// Whether readers should be made aware of a caveat: the result is
+        /// provisional or the issue has an annotation.
+        pub fn needs_attention(&self) -> bool {
+            self.provisional || self.has_annotation()
+        }
+
+        /// Acceptance computed from the votes, to cross-check `issue_accepted`. `None`
+        /// while the result is provisional or counting is not completed.
+        pub fn is_accepted_computed(&self) -> Option<bool> {
+            (self.issue_completed && !self.provisional).then(|| self.passes())
+        }
+
+        pub fn get_title(&self, lang: Lang) -> Option<&str> {
+            self.issue_title.iter().find_map(|title| {
+                if title.lang == lang && !title.text.chars().all(char::is_whitespace) {
+                    Some(title.text.as_str())
+                } else {
+                    None
+                }
+            })
+        }
+
+        /// The first available title among `preferred`.
+        pub fn get_title_with_fallback(&self, preferred: &[Lang]) -> Option<&str> {
+            preferred.iter().find_map(|&lang| self.get_title(lang))
+        }
+
+        /// The title in `lang`, falling back to German and then French.
+        pub fn get_title_or_default(&self, lang: Lang) -> Option<&str> {
+            self.get_title_with_fallback(&[lang, Lang::DE, Lang::FR])
+        }
+
+        pub fn title_index(&self) -> TitleIndex<'_> {
+            TitleIndex::new(&self.issue_title)
+        }
+
+        /// Languages with a non-empty title. Romansh titles are often missing.
+        pub fn available_languages(&self) -> Vec<Lang> {
+            let mut langs = Vec::new();
+            for title in &self.issue_title {
+                if !title.text.chars().all(char::is_whitespace) && !langs.contains(&title.lang) {
+                    langs.push(title.lang);
+                }
+            }
+            langs
+        }
+
+        /// Every commune of the issue together with its canton.
+        pub fn all_communes(&self) -> impl Iterator<Item = (&Canton, &Commune)> {
+            self.cantons.iter().flat_map(|canton| {
+                canton
+                    .communes
+                    .iter()
+                    .flatten()
+                    .map(move |commune| (canton, commune))
+            })
+        }
+
+        /// Every district of the issue together with its canton.
+        pub fn all_districts(&self) -> impl Iterator<Item = (&Canton, &District)> {
+            self.cantons.iter().flat_map(|canton| {
+                canton
+                    .districts
+                    .iter()
+                    .flatten()
+                    .map(move |district| (canton, district))
+            })
+        }
+
+        /// The canton whose number, or the number of one of whose districts, is the
+        /// `geo_level_parentnumber` of `commune`.
+        pub fn canton_of_commune(&self, commune: &Commune) -> Option<&Canton> {
+            let parent = commune.geo_level_parentnumber.as_str();
+            self.cantons.iter().find(|canton| {
+                canton.geo_levelnumber == parent
+                    || canton
+                        .districts
+                        .iter()
+                        .flatten()
+                        .any(|district| district.geo_levelnumber == parent)
+            })
+        }
+
+        /// The communes of the issue keyed by their BFS number.
+        pub fn commune_index(&self) -> HashMap<&str, &Commune> {
+            self.all_communes()
+                .map(|(_, commune)| (commune.geo_levelnumber.as_str(), commune))
+                .collect()
+        }
+
+        /// Results of all communes keyed by BFS number, see [`CommuneResult`].
+        pub fn commune_results(&self, skip_invalid: bool) -> Result<HashMap<u32, CommuneResult>> {
+            super::commune_results(
+                self.all_communes().map(|(_, commune)| commune),
+                skip_invalid,
+            )
+        }
+
+        /// The outcomes of all geo levels of kind `level`, e.g. of every commune.
+        /// Empty if the issue has no such geo levels.
+        pub fn outcomes_at(&self, level: GeoLevel) -> impl Iterator<Item = &Outcome> {
+            fn listed<T>(list: &Option<Vec<T>>, selected: bool) -> &[T] {
+                list.as_deref().filter(|_| selected).unwrap_or_default()
+            }
+
+            let country = (level == GeoLevel::Country).then_some(&self.outcome);
+            let cantons = self.cantons.iter().flat_map(move |canton| {
+                let districts = listed(&canton.districts, level == GeoLevel::District);
+                let communes = listed(&canton.communes, level == GeoLevel::Commune);
+                let constituencies =
+                    listed(&canton.constituencies, level == GeoLevel::Constituency);
+                (level == GeoLevel::Canton)
+                    .then_some(&canton.outcome)
+                    .into_iter()
+                    .chain(districts.iter().map(|district| &district.outcome))
+                    .chain(communes.iter().map(|commune| &commune.outcome))
+                    .chain(
+                        constituencies
+                            .iter()
+                            .map(|constituency| &constituency.outcome),
+                    )
+            });
+            country.into_iter().chain(cantons)
+        }
+
+        /// Writes the communes as CSV, one row per commune with the name of its
+        /// canton, the counts, turnout and yes ratio. The header is in `lang`.
+        #[cfg(feature = "csv")]
+        pub fn to_csv(&self, lang: Lang, writer: impl Write) -> Result<()> {
+            let communes = self
+                .all_communes()
+                .map(|(canton, commune)| (canton.geo_levelname.as_str(), commune));
+            write_communes_csv(communes, lang, writer)
+        }
+
+        pub fn completed_communes(&self) -> impl Iterator<Item = &Commune> {
+            self.all_communes()
+                .map(|(_, commune)| commune)
+                .filter(|commune| commune.outcome.count_completed)
+        }
+
+        /// Fraction of the communes that finished counting, or of the cantons if
+        /// there are no communes.
+        pub fn counting_progress(&self) -> f64 {
+            let communes = self.all_communes().count();
+            if communes > 0 {
+                return self.completed_communes().count() as f64 / communes as f64;
+            }
+            let completed = self
+                .cantons
+                .iter()
+                .filter(|canton| canton.outcome.count_completed)
+                .count();
+            ratio(completed as u32, self.cantons.len() as u32).unwrap_or(0.0)
+        }
+
+        /// Sum of the canton outcomes, only completed if every canton is.
+        pub fn aggregate_cantons(&self) -> Outcome {
+            self.cantons.iter().map(|canton| canton.outcome).sum()
+        }
+
+        /// Outcome recomputed from the lowest geo level of each canton, see
+        /// [`Canton::sub_level_outcome`], using the canton itself where no lower
+        /// level is present.
+        pub fn computed_outcome(&self) -> Outcome {
+            self.cantons
+                .iter()
+                .map(|canton| canton.sub_level_outcome().unwrap_or(canton.outcome))
+                .sum()
+        }
+
+        /// Eligible voters summed like [`Issue::computed_outcome`]: per canton from
+        /// its communes, else its districts, else the canton itself. Constituencies
+        /// subdivide communes and are never added on top.
+        pub fn total_eligible_voters(&self) -> u32 {
+            self.computed_outcome().eligible_voters
+        }
+
+        /// [`Outcome::turnout`] of [`Issue::computed_outcome`], aggregated from the
+        /// same geo levels as [`Issue::total_eligible_voters`].
+        pub fn national_turnout(&self) -> Option<f64> {
+            self.computed_outcome().turnout()
+        }
+
+        /// The reported national outcome minus [`Issue::computed_outcome`].
+        pub fn aggregate_mismatch(&self) -> OutcomeDelta {
+            self.outcome.delta(&self.computed_outcome())
+        }
+
+        /// How far the reported national outcome is ahead of the sum of the cantons.
+        pub fn aggregation_lag(&self) -> OutcomeDelta {
+            self.outcome.delta(&self.aggregate_cantons())
+        }
+
+        /// Yes ratio of a fully counted canton minus the national yes ratio.
+        pub fn canton_vs_national(&self, canton_number: u8) -> Option<f64> {
+            let canton = self
+                .cantons
+                .iter()
+                .find(|canton| canton.geo_levelnumber == canton_number.to_string())?;
+            if !canton.outcome.count_completed {
+                return None;
+            }
+            Some(canton.outcome.yes_ratio()? - self.outcome.yes_ratio()?)
+        }
+
+        /// Share of the national yes votes each canton contributed, keyed by canton number.
+        pub fn support_weighted_by_canton(&self) -> Vec<(u8, f64)> {
+            let total: u32 = self.cantons.iter().map(|c| c.outcome.yes_votes).sum();
+            if total == 0 {
+                return Vec::new();
+            }
+            self.cantons
+                .iter()
+                .filter_map(|canton| {
+                    let number = canton.geo_levelnumber.parse().ok()?;
+                    Some((number, canton.outcome.yes_votes as f64 / total as f64))
+                })
+                .collect()
+        }
+
+        /// Ratio of invalid votes per canton, `None` for cantons without cast ballots.
+        pub fn canton_invalid_rates(&self) -> Vec<(&Canton, Option<f64>)> {
+            self.cantons
+                .iter()
+                .map(|canton| (canton, canton.outcome.invalid_votes_ratio()))
+                .collect()
+        }
+
+        /// Number of fully counted communes whose turnout exceeds `threshold`, and the
+        /// number of fully counted communes overall.
+        pub fn communes_above_turnout(&self, threshold: f64) -> (usize, usize) {
+            self.completed_communes()
+                .fold((0, 0), |(above, total), commune| {
+                    if commune
+                        .outcome
+                        .turnout()
+                        .is_some_and(|turnout| turnout > threshold)
+                    {
+                        (above + 1, total + 1)
+                    } else {
+                        (above, total + 1)
+                    }
+                })
+        }
+
+        /// Projected national yes ratio. Counted communes contribute their actual votes;
+        /// uncounted communes are weighted by their eligible voters times the turnout
+        /// seen so far and take their yes ratio from `baselines` (keyed by BFS number),
+        /// falling back to the yes ratio of the counted communes.
+        pub fn project_with_baselines(&self, baselines: &HashMap<String, f64>) -> Option<f64> {
+            let communes = || self.all_communes().map(|(_, commune)| commune);
+            let (yes, valid, eligible) = communes()
+                .filter(|commune| commune.outcome.count_completed)
+                .fold((0.0, 0.0, 0.0), |(yes, valid, eligible), commune| {
+                    (
+                        yes + commune.outcome.yes_votes as f64,
+                        valid + commune.outcome.valid_votes() as f64,
+                        eligible + commune.outcome.eligible_voters as f64,
+                    )
+                });
+            let counted_ratio = (valid > 0.0).then(|| yes / valid);
+            let turnout = if eligible > 0.0 {
+                valid / eligible
+            } else {
+                1.0
+            };
+            let (mut yes, mut valid) = (yes, valid);
+            for commune in communes().filter(|commune| !commune.outcome.count_completed) {
+                let ratio = baselines
+                    .get(&commune.geo_levelnumber)
+                    .copied()
+                    .or(counted_ratio)?;
+                let expected = commune.outcome.eligible_voters as f64 * turnout;
+                yes += expected * ratio;
+                valid += expected;
+            }
+            (valid > 0.0).then(|| yes / valid)
+        }
+
+        /// Cantons whose result, if flipped from yes to no or vice versa, would change
+        /// whether the canton majority is reached. Only fully counted cantons are
+        /// considered; a canton votes yes if it has more yes than no votes, and the
+        /// canton majority is reached if the yes weight exceeds half the total
+        /// weight of [`Issue::outcome_cantons`], with half cantons weighing 0.5.
+        pub fn pivotal_cantons(&self) -> Vec<&Canton> {
+            if !self.double_majority {
+                return Vec::new();
+            }
+            let half = self.outcome_cantons.total_canton_weight() / 2.0;
+            let counted = self.cantons.iter().filter(|c| c.outcome.count_completed);
+            let yes: f64 = counted
+                .clone()
+                .filter(|canton| canton.outcome.yes_votes > canton.outcome.no_votes)
+                .map(Canton::canton_weight)
+                .sum();
+            let reached = yes > half;
+            counted
+                .filter(|canton| {
+                    let weight = canton.canton_weight();
+                    let flipped = if canton.outcome.yes_votes > canton.outcome.no_votes {
+                        yes - weight > half
+                    } else {
+                        yes + weight > half
+                    };
+                    flipped != reached
+                })
+                .collect()
+        }
+
+        /// Fully counted cantons with more yes than no votes.
+        pub fn cantons_accepting(&self) -> Vec<&Canton> {
+            self.canton_split().0
+        }
+
+        /// Fully counted cantons with at least as many no as yes votes.
+        pub fn cantons_rejecting(&self) -> Vec<&Canton> {
+            self.canton_split().1
+        }
+
+        /// The cantons split into accepting, rejecting and not yet fully counted.
+        pub fn canton_split(&self) -> (Vec<&Canton>, Vec<&Canton>, Vec<&Canton>) {
+            let mut split = (Vec::new(), Vec::new(), Vec::new());
+            for canton in &self.cantons {
+                let outcome = &canton.outcome;
+                if !outcome.count_completed {
+                    split.2.push(canton);
+                } else if outcome.yes_votes > outcome.no_votes {
+                    split.0.push(canton);
+                } else {
+                    split.1.push(canton);
+                }
+            }
+            split
+        }
+
+        /// Fully counted cantons with their turnout, highest first. Cantons with the
+        /// same turnout keep their order.
+        pub fn cantons_by_turnout(&self) -> Vec<(&Canton, f64)> {
+            let mut cantons: Vec<_> = self
+                .cantons
+                .iter()
+                .filter(|canton| canton.outcome.count_completed)
+                .filter_map(|canton| Some((canton, canton.outcome.turnout()?)))
+                .collect();
+            cantons.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+            cantons
+        }
+
+        pub fn highest_turnout_canton(&self) -> Option<(&Canton, f64)> {
+            self.cantons_by_turnout().first().copied()
+        }
+
+        pub fn lowest_turnout_canton(&self) -> Option<(&Canton, f64)> {
+            self.cantons_by_turnout().last().copied()
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Country {
+        #[serde(rename = "geoLevelnummer")]
+        pub geo_levelnumber: u8,
+        #[serde(rename = "geoLevelname")]
+        pub geo_levelname: String,
+        #[serde(rename = "nochKeineInformation")]
+        pub no_infos_yet: bool,
+        #[serde(rename = "vorlagen")]
+        pub issues: Vec<Issue>,
+    }
+
+    impl Country {
+        pub fn issue(&self, id: u32) -> Option<&Issue> {
+            self.issues.iter().find(|issue| issue.issue_id == id)
+        }
+
+        /// Issues that are not a counter-proposal or tie-breaker of another issue.
+        pub fn main_issues(&self) -> impl Iterator<Item = &Issue> {
+            self.issues
+                .iter()
+                .filter(|issue| issue.main_issue_id == issue.issue_id)
+        }
+
+        /// Issues belonging to the main issue with id `main_id`, e.g. its counter-proposal.
+        pub fn sub_issues_of(&self, main_id: u32) -> impl Iterator<Item = &Issue> {
+            self.issues
+                .iter()
+                .filter(move |issue| issue.main_issue_id == main_id && issue.issue_id != main_id)
+        }
+    }
+
+    /// Serializes back into the OGD schema, with the original field names. The
+    /// shape is normalized on the way: unknown fields are dropped, optional fields
+    /// are always written, as `null` if they were missing, geo levels that were
+    /// missing or empty arrays become `null`, and outcomes nested in `zahlen` are
+    /// flattened into `resultat`. Files already in that shape round-trip unchanged.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Data {
+        pub abstimmtag: String,
+        pub timestamp: String,
+        #[serde(rename = "schweiz")]
+        pub country: Country,
+    }
+
+    impl Data {
+        pub fn from_json(json: &str) -> Result<Data> {
+            Ok(serde_json::from_str(json)?)
+        }
+
+        pub fn from_reader(reader: impl Read) -> Result<Data> {
+            Ok(serde_json::from_reader(reader)?)
+        }
+
+        /// Parses `abstimmtag`, e.g. `20240922`.
+        #[cfg(feature = "chrono")]
+        pub fn voting_day(&self) -> std::result::Result<chrono::NaiveDate, chrono::ParseError> {
+            chrono::NaiveDate::parse_from_str(&self.abstimmtag, "%Y%m%d")
+        }
+
+        /// Parses `timestamp`, e.g. `2024-09-22T17:12:34.123Z`.
+        #[cfg(feature = "chrono")]
+        pub fn generated_at(
+            &self,
+        ) -> std::result::Result<chrono::DateTime<chrono::Utc>, chrono::ParseError> {
+            chrono::DateTime::parse_from_rfc3339(&self.timestamp).map(|date| date.to_utc())
+        }
+
+        /// Issues whose title contains `query`, see [`Issue::get_title`].
+        pub fn find_issues_by_title(&self, query: &str, lang: Option<Lang>) -> Vec<&Issue> {
+            self.country
+                .issues
+                .iter()
+                .filter(|issue| titles_contain(&issue.issue_title, query, lang))
+                .collect()
+        }
+
+        pub fn status_summary(&self) -> StatusSummary {
+            let issues = &self.country.issues;
+            let completed_issues = issues.iter().filter(|issue| issue.issue_completed).count();
+            StatusSummary {
+                completed_issues,
+                pending_issues: issues.len() - completed_issues,
+                any_provisional: issues.iter().any(|issue| issue.provisional),
+                no_infos_yet: self.country.no_infos_yet,
+            }
+        }
+
+        /// BFS numbers listed more than once among the communes of the same issue.
+        pub fn duplicate_commune_numbers(&self) -> Vec<String> {
+            let mut duplicates = BTreeSet::new();
+            for issue in &self.country.issues {
+                let mut seen = HashSet::new();
+                for (_, commune) in issue.all_communes() {
+                    if !seen.insert(commune.geo_levelnumber.as_str()) {
+                        duplicates.insert(commune.geo_levelnumber.clone());
+                    }
+                }
+            }
+            duplicates.into_iter().collect()
+        }
+
+        /// Cantons whose outcome differs from [`Canton::sub_level_outcome`], for
+        /// every issue, i.e. those that [`Canton::reconcile`] reports as a mismatch.
+        /// Cantons without communes or districts are skipped.
+        pub fn reconciliation_failures(&self) -> Vec<ReconciliationFailure> {
+            let mut failures = Vec::new();
+            for issue in &self.country.issues {
+                for canton in &issue.cantons {
+                    let Some(sum) = canton.sub_level_outcome() else {
+                        continue;
+                    };
+                    let delta = canton.outcome.delta(&sum);
+                    if !delta.is_zero() {
+                        failures.push(ReconciliationFailure {
+                            issue_id: issue.issue_id,
+                            canton: canton.geo_levelnumber.clone(),
+                            delta,
+                        });
+                    }
+                }
+            }
+            failures
+        }
+
+        /// BFS numbers of communes without any eligible voters.
+        pub fn zero_electorate_communes(&self) -> Vec<String> {
+            let communes = self.country.issues.iter().flat_map(Issue::all_communes);
+            communes
+                .map(|(_, commune)| commune)
+                .filter(|commune| commune.outcome.eligible_voters == 0)
+                .map(|commune| commune.geo_levelnumber.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        }
+
+        /// Numbers of the 26 cantons missing from each issue, keyed by issue id.
+        /// Issues listing all cantons are left out.
+        pub fn missing_cantons(&self) -> BTreeMap<u32, Vec<u8>> {
+            self.country
+                .issues
+                .iter()
+                .filter_map(|issue| {
+                    let missing: Vec<u8> = (1..=26)
+                        .filter(|number| {
+                            !issue
+                                .cantons
+                                .iter()
+                                .any(|canton| canton.geo_levelnumber == number.to_string())
+                        })
+                        .collect();
+                    (!missing.is_empty()).then_some((issue.issue_id, missing))
+                })
+                .collect()
+        }
+
+        /// Ids of final issues whose `issue_accepted` flag contradicts their votes.
+        pub fn inconsistent_accepted_issues(&self) -> Vec<u32> {
+            self.country
+                .issues
+                .iter()
+                .filter(|issue| {
+                    issue
+                        .is_accepted_computed()
+                        .is_some_and(|accepted| accepted != issue.issue_accepted)
+                })
+                .map(|issue| issue.issue_id)
+                .collect()
+        }
+
+        pub fn diff(&self, previous: &Data) -> DataDiff {
+            fn issues(data: &Data) -> Vec<(u32, &Issue)> {
+                data.country
+                    .issues
+                    .iter()
+                    .map(|issue| (issue.issue_id, issue))
+                    .collect()
+            }
+
+            diff_issues(issues(self), issues(previous), |issue| {
+                let mut entries = vec![(
+                    GeoLevel::Country,
+                    self.country.geo_levelnumber.to_string().into(),
+                    self.country.geo_levelname.as_str(),
+                    issue.outcome,
+                )];
+                for canton in &issue.cantons {
+                    let number = canton.geo_levelnumber.as_str().into();
+                    let name = canton.geo_levelname.as_str();
+                    entries.push((GeoLevel::Canton, number, name, canton.outcome));
+                    entries.extend(geo_entries(
+                        &canton.districts,
+                        &canton.communes,
+                        &canton.constituencies,
+                    ));
+                }
+                entries
+            })
+        }
+
+        /// Runs all the checks above at once.
+        pub fn validate(&self) -> ValidationReport {
+            ValidationReport {
+                reconciliation_failures: self.reconciliation_failures(),
+                duplicate_commune_numbers: self.duplicate_commune_numbers(),
+                zero_electorate_communes: self.zero_electorate_communes(),
+                missing_cantons: self.missing_cantons(),
+                inconsistent_accepted_issues: self.inconsistent_accepted_issues(),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct ReconciliationFailure {
+        pub issue_id: u32,
+        pub canton: String,
+        /// Canton outcome minus the sum of its communes or districts.
+        pub delta: OutcomeDelta,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+    pub struct ValidationReport {
+        pub reconciliation_failures: Vec<ReconciliationFailure>,
+        pub duplicate_commune_numbers: Vec<String>,
+        pub zero_electorate_communes: Vec<String>,
+        pub missing_cantons: BTreeMap<u32, Vec<u8>>,
+        pub inconsistent_accepted_issues: Vec<u32>,
+    }
+
+    impl ValidationReport {
+        pub fn is_valid(&self) -> bool {
+            *self == Self::default()
+        }
+    }
+
+    /// Id of the CKAN package with the realtime results.
+    pub const PACKAGE_ID: &str =
+        "echtzeitdaten-am-abstimmungstag-zu-eidgenoessischen-abstimmungsvorlagen";
+
+    #[cfg(feature = "net")]
+    pub async fn get_data_by_url(url: &str) -> Result<Data> {
+        Client::builder().build()?.national_by_url(url).await
+    }
+
+    #[cfg(feature = "net")]
+    pub async fn get_latest() -> Result<Data> {
+        Client::builder().build()?.national_latest().await
+    }
+
+    /// URL of the static file with the results of the voting day `year`-`month`-`day`.
+    pub fn url_for_date(year: i32, month: u32, day: u32) -> String {
+        static_url("eidgAbstimmung", year, month, day)
+    }
+
+    /// Fetches the results of a past voting day without going through CKAN.
+    #[cfg(feature = "net")]
+    pub async fn get_by_date(year: i32, month: u32, day: u32) -> Result<Data> {
+        Client::builder()
+            .build()?
+            .national_by_date(year, month, day)
+            .await
+    }
+
+    /// Synchronous versions of the fetch functions, for use outside of an async runtime.
+    #[cfg(feature = "blocking")]
+    pub mod blocking {
+        use super::*;
+
+        pub fn get_data_by_url(url: &str) -> Result<Data> {
+            Data::from_json(&blocking_get_text(url)?)
+        }
+
+        pub fn get_latest() -> Result<Data> {
+            let response = blocking_get_text(&package_url(PACKAGE_ID))?;
+            let url = latest_resource_url(parse_resources(&response)?)?;
+            get_data_by_url(&url)
+        }
+    }
+
+    /// Like [`get_latest`], but if the CKAN lookup fails, makes a best-effort attempt
+    /// at the static file of the last Sunday, assuming it was a voting day. If that
+    /// file cannot be fetched either, returns the error of the CKAN lookup.
+    #[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+    pub async fn get_latest_with_fallback() -> Result<Data> {
+        let client = Client::builder().build()?;
+        let url = match client.latest_url(PACKAGE_ID).await {
+            Ok(url) => url,
+            Err(err) => {
+                return client
+                    .national_by_url(&guess_static_url(url_for_date))
+                    .await
+                    .map_err(|_| err);
+            }
+        };
+        client.national_by_url(&url).await
+    }
+}
+
+pub mod cantonal {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Issue {
+        #[serde(rename = "vorlagenId")]
+        pub issue_id: u32,
+        #[serde(rename = "reihenfolgeAnzeige")]
+        pub display_order: u32,
+        #[serde(rename = "vorlagenTitel")]
+        pub issue_title: Vec<IssueTitle>,
+        #[serde(rename = "vorlageBeendet")]
+        pub issue_completed: bool,
+        #[serde(rename = "vorlageAngenommen")]
+        pub issue_accepted: bool,
+        #[serde(rename = "vorlagenArtId")]
+        pub issue_type_id: u32,
+        #[serde(rename = "hauptvorlagenId")]
+        pub main_issue_id: Option<u32>,
+        #[serde(rename = "resultat")]
+        pub outcome: Outcome,
+        #[serde(rename = "bezirke", default, deserialize_with = "non_empty")]
+        pub districts: Option<Vec<District>>,
+        #[serde(rename = "gemeinden", default, deserialize_with = "non_empty")]
+        pub communes: Option<Vec<Commune>>,
+        #[serde(rename = "zaehlkreise", default, deserialize_with = "non_empty")]
+        pub constituencies: Option<Vec<Constituency>>,
+    }
+
+    impl Issue {
+        pub fn issue_type(&self) -> IssueType {
+            IssueType::from_id(self.issue_type_id)
+        }
+
+        /// Always `false`, cantonal issues are decided by the voters alone.
+        pub fn requires_canton_majority(&self) -> bool {
+            false
+        }
+
+        /// Whether the issue passes with the votes counted so far.
+        pub fn passes(&self) -> bool {
+            self.outcome.yes_votes > self.outcome.no_votes
+        }
+
+        pub fn get_title(&self, lang: Lang) -> Option<&str> {
+            self.issue_title.iter().find_map(|title| {
+                if title.lang == lang && !title.text.chars().all(char::is_whitespace) {
+                    Some(title.text.as_str())
+                } else {
+                    None
+                }
+            })
+        }
+
+        /// The first available title among `preferred`.
+        pub fn get_title_with_fallback(&self, preferred: &[Lang]) -> Option<&str> {
+            preferred.iter().find_map(|&lang| self.get_title(lang))
+        }
+
+        /// The title in `lang`, falling back to German and then French.
+        pub fn get_title_or_default(&self, lang: Lang) -> Option<&str> {
+            self.get_title_with_fallback(&[lang, Lang::DE, Lang::FR])
+        }
+
+        pub fn title_index(&self) -> TitleIndex<'_> {
+            TitleIndex::new(&self.issue_title)
+        }
+
+        /// Languages with a non-empty title. Romansh titles are often missing.
+        pub fn available_languages(&self) -> Vec<Lang> {
+            let mut langs = Vec::new();
+            for title in &self.issue_title {
+                if !title.text.chars().all(char::is_whitespace) && !langs.contains(&title.lang) {
+                    langs.push(title.lang);
+                }
+            }
+            langs
+        }
+
+        pub fn geo_levels(&self) -> GeoLevelKind {
+            GeoLevelKind::of(
+                self.districts.is_some(),
+                self.communes.is_some(),
+                self.constituencies.is_some(),
+            )
+        }
+
+        /// Results of all communes keyed by BFS number, see [`CommuneResult`].
+        pub fn commune_results(&self, skip_invalid: bool) -> Result<HashMap<u32, CommuneResult>> {
+            super::commune_results(self.communes.iter().flatten(), skip_invalid)
+        }
+
+        /// Like [`national::Issue::to_csv`], with `canton` being the canton the
+        /// issue belongs to.
+        #[cfg(feature = "csv")]
+        pub fn to_csv(&self, canton: &Canton, lang: Lang, writer: impl Write) -> Result<()> {
+            let communes = self
+                .communes
+                .iter()
+                .flatten()
+                .map(|commune| (canton.geo_levelname.as_str(), commune));
+            write_communes_csv(communes, lang, writer)
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Canton {
+        #[serde(rename = "geoLevelnummer")]
+        pub geo_levelnumber: u8,
+        #[serde(rename = "geoLevelname")]
+        pub geo_levelname: String,
+        #[serde(rename = "nochKeineInformation")]
+        pub no_infos_yet: bool,
+        #[serde(rename = "vorlagen")]
+        pub issues: Vec<Issue>,
+    }
+
+    impl Canton {
+        pub fn canton(&self) -> Option<super::Canton> {
+            super::Canton::from_number(self.geo_levelnumber)
+        }
+
+        pub fn issue(&self, id: u32) -> Option<&Issue> {
+            self.issues.iter().find(|issue| issue.issue_id == id)
+        }
+
+        /// Issues that are not a counter-proposal or tie-breaker of another issue.
+        pub fn main_issues(&self) -> impl Iterator<Item = &Issue> {
+            self.issues
+                .iter()
+                .filter(|issue| issue.main_issue_id.is_none_or(|id| id == issue.issue_id))
+        }
+
+        /// Issues belonging to the main issue with id `main_id`, e.g. its counter-proposal.
+        pub fn sub_issues_of(&self, main_id: u32) -> impl Iterator<Item = &Issue> {
+            self.issues.iter().filter(move |issue| {
+                issue.main_issue_id == Some(main_id) && issue.issue_id != main_id
+            })
+        }
+    }
+
+    /// Serializes back into the OGD schema, see [`national::Data`].
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Data {
+        pub abstimmtag: String,
+        pub timestamp: String,
+        #[serde(rename = "kantone")]
+        pub kantone: Vec<Canton>,
+    }
+
+    impl Data {
+        pub fn from_json(json: &str) -> Result<Data> {
+            Ok(serde_json::from_str(json)?)
+        }
+
+        pub fn from_reader(reader: impl Read) -> Result<Data> {
+            Ok(serde_json::from_reader(reader)?)
+        }
+
+        /// Parses `abstimmtag`, e.g. `20240922`.
+        #[cfg(feature = "chrono")]
+        pub fn voting_day(&self) -> std::result::Result<chrono::NaiveDate, chrono::ParseError> {
+            chrono::NaiveDate::parse_from_str(&self.abstimmtag, "%Y%m%d")
+        }
+
+        /// Parses `timestamp`, e.g. `2024-09-22T17:12:34.123Z`.
+        #[cfg(feature = "chrono")]
+        pub fn generated_at(
+            &self,
+        ) -> std::result::Result<chrono::DateTime<chrono::Utc>, chrono::ParseError> {
+            chrono::DateTime::parse_from_rfc3339(&self.timestamp).map(|date| date.to_utc())
+        }
+
+        /// Issues of all cantons whose title contains `query`, together with their
+        /// canton.
+        pub fn find_issues_by_title(
+            &self,
+            query: &str,
+            lang: Option<Lang>,
+        ) -> Vec<(&Canton, &Issue)> {
+            self.kantone
+                .iter()
+                .flat_map(|canton| canton.issues.iter().map(move |issue| (canton, issue)))
+                .filter(|(_, issue)| titles_contain(&issue.issue_title, query, lang))
+                .collect()
+        }
+
+        /// Counts the issues of all cantons. Cantonal results are never provisional,
+        /// and there are no infos yet as long as no canton has reported any.
+        pub fn status_summary(&self) -> StatusSummary {
+            let issues = || self.kantone.iter().flat_map(|canton| &canton.issues);
+            let completed_issues = issues().filter(|issue| issue.issue_completed).count();
+            StatusSummary {
+                completed_issues,
+                pending_issues: issues().count() - completed_issues,
+                any_provisional: false,
+                no_infos_yet: self.kantone.iter().all(|canton| canton.no_infos_yet),
+            }
+        }
+
+        pub fn diff(&self, previous: &Data) -> DataDiff {
+            fn issues(data: &Data) -> Vec<(u32, (&Canton, &Issue))> {
+                data.kantone
+                    .iter()
+                    .flat_map(|canton| {
+                        canton
+                            .issues
+                            .iter()
+                            .map(move |issue| (issue.issue_id, (canton, issue)))
+                    })
+                    .collect()
+            }
+
+            diff_issues(issues(self), issues(previous), |(canton, issue)| {
+                let number = canton.geo_levelnumber.to_string().into();
+                let name = canton.geo_levelname.as_str();
+                let mut entries = vec![(GeoLevel::Canton, number, name, issue.outcome)];
+                entries.extend(geo_entries(
+                    &issue.districts,
+                    &issue.communes,
+                    &issue.constituencies,
+                ));
+                entries
+            })
+        }
+    }
+
+    /// Id of the CKAN package with the realtime results.
+    pub const PACKAGE_ID: &str =
+        "echtzeitdaten-am-abstimmungstag-zu-kantonalen-abstimmungsvorlagen";
+
+    #[cfg(feature = "net")]
+    pub async fn get_data_by_url(url: &str) -> Result<Data> {
+        Client::builder().build()?.cantonal_by_url(url).await
+    }
+
+    #[cfg(feature = "net")]
+    pub async fn get_latest() -> Result<Data> {
+        Client::builder().build()?.cantonal_latest().await
+    }
+
+    /// URL of the static file with the results of the voting day `year`-`month`-`day`.
+    pub fn url_for_date(year: i32, month: u32, day: u32) -> String {
+        static_url("kantAbstimmung", year, month, day)
+    }
+
+    /// Fetches the results of a past voting day without going through CKAN.
+    #[cfg(feature = "net")]
+    pub async fn get_by_date(year: i32, month: u32, day: u32) -> Result<Data> {
+        Client::builder()
+            .build()?
+            .cantonal_by_date(year, month, day)
+            .await
+    }
+
+    /// Synchronous versions of the fetch functions, for use outside of an async runtime.
+    #[cfg(feature = "blocking")]
+    pub mod blocking {
+        use super::*;
+
+        pub fn get_data_by_url(url: &str) -> Result<Data> {
+            Data::from_json(&blocking_get_text(url)?)
+        }
+
+        pub fn get_latest() -> Result<Data> {
+            let response = blocking_get_text(&package_url(PACKAGE_ID))?;
+            let url = latest_resource_url(parse_resources(&response)?)?;
+            get_data_by_url(&url)
+        }
+    }
+
+    /// Like [`get_latest`], but if the CKAN lookup fails, makes a best-effort attempt
+    /// at the static file of the last Sunday, assuming it was a voting day. If that
+    /// file cannot be fetched either, returns the error of the CKAN lookup.
+    #[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+    pub async fn get_latest_with_fallback() -> Result<Data> {
+        let client = Client::builder().build()?;
+        let url = match client.latest_url(PACKAGE_ID).await {
+            Ok(url) => url,
+            Err(err) => {
+                return client
+                    .cantonal_by_url(&guess_static_url(url_for_date))
+                    .await
+                    .map_err(|_| err);
+            }
+        };
+        client.cantonal_by_url(&url).await
+    }
+}
+
+/// The national and cantonal data of the same voting day.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VotingDay {
+    pub national: national::Data,
+    pub cantonal: cantonal::Data,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueRef<'a> {
+    National(&'a national::Issue),
+    /// A cantonal issue together with the number of its canton.
+    Cantonal(u8, &'a cantonal::Issue),
+}
+
+impl VotingDay {
+    /// All national issues followed by the issues of every canton.
+    pub fn all_issues(&self) -> Vec<IssueRef<'_>> {
+        let national = self.national.country.issues.iter().map(IssueRef::National);
+        let cantonal = self.cantonal.kantone.iter().flat_map(|canton| {
+            canton
+                .issues
+                .iter()
+                .map(|issue| IssueRef::Cantonal(canton.geo_levelnumber, issue))
+        });
+        national.chain(cantonal).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NATIONAL_JSON: &str = r#"{
+        "abstimmtag": "20240922",
+        "timestamp": "2024-09-22T17:12:34.123Z",
+        "schweiz": {
+            "geoLevelnummer": 0,
+            "geoLevelname": "Schweiz",
+            "nochKeineInformation": false,
+            "vorlagen": [
+                {
+                    "vorlagenId": 6760,
+                    "reihenfolgeAnzeige": 1,
+                    "vorlagenTitel": [
+                        { "langKey": "de", "text": "Biodiversitätsinitiative" },
+                        { "langKey": "fr", "text": "Initiative biodiversité" },
+                        { "langKey": "it", "text": "Iniziativa biodiversità" },
+                        { "langKey": "rm", "text": " " },
+                        { "langKey": "en", "text": "Biodiversity initiative" }
+                    ],
+                    "vorlageBeendet": true,
+                    "provisorisch": false,
+                    "vorlageAngenommen": false,
+                    "vorlagenArtId": 3,
+                    "hauptvorlagenId": 6760,
+                    "reserveInfoText": null,
+                    "doppeltesMehr": true,
+                    "staende": {
+                        "jaStaendeGanz": 1,
+                        "neinStaendeGanz": 0,
+                        "anzahlStaendeGanz": 1,
+                        "jaStaendeHalb": 0,
+                        "neinStaendeHalb": 2,
+                        "anzahlStaendeHalb": 2
+                    },
+                    "resultat": {
+                        "gebietAusgezaehlt": true,
+                        "jaStimmenAbsolut": 640,
+                        "neinStimmenAbsolut": 560,
+                        "eingelegteStimmzettel": 1230,
+                        "anzahlStimmberechtigte": 2450
+                    },
+                    "kantone": [
+                        {
+                            "geoLevelnummer": "1",
+                            "geoLevelname": "Zürich",
+                            "resultat": {
+                                "gebietAusgezaehlt": true,
+                                "jaStimmenAbsolut": 600,
+                                "neinStimmenAbsolut": 400,
+                                "eingelegteStimmzettel": 1020,
+                                "anzahlStimmberechtigte": 2000
+                            },
+                            "bezirke": [
+                                {
+                                    "geoLevelnummer": "101",
+                                    "geoLevelname": "Bezirk Affoltern",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 150,
+                                        "neinStimmenAbsolut": 100,
+                                        "eingelegteStimmzettel": 260,
+                                        "anzahlStimmberechtigte": 500
+                                    }
+                                },
+                                {
+                                    "geoLevelnummer": "112",
+                                    "geoLevelname": "Bezirk Zürich",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 450,
+                                        "neinStimmenAbsolut": 300,
+                                        "eingelegteStimmzettel": 760,
+                                        "anzahlStimmberechtigte": 1500
+                                    }
+                                }
+                            ],
+                            "gemeinden": [
+                                {
+                                    "geoLevelnummer": "1",
+                                    "geoLevelname": "Aeugst am Albis",
+                                    "geoLevelParentnummer": "101",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 150,
+                                        "neinStimmenAbsolut": 100,
+                                        "eingelegteStimmzettel": 260,
+                                        "anzahlStimmberechtigte": 500
+                                    }
+                                },
+                                {
+                                    "geoLevelnummer": "261",
+                                    "geoLevelname": "Zürich",
+                                    "geoLevelParentnummer": "112",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 450,
+                                        "neinStimmenAbsolut": 300,
+                                        "eingelegteStimmzettel": 760,
+                                        "anzahlStimmberechtigte": 1500
+                                    }
+                                }
+                            ],
+                            "zaehlkreise": [
+                                {
+                                    "geoLevelnummer": "10261",
+                                    "geoLevelname": "Zürich Kreis 1+2",
+                                    "geoLevelParentnummer": "261",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 200,
+                                        "neinStimmenAbsolut": 100,
+                                        "eingelegteStimmzettel": 305,
+                                        "anzahlStimmberechtigte": 600
+                                    }
+                                },
+                                {
+                                    "geoLevelnummer": "20261",
+                                    "geoLevelname": "Zürich Kreis 3",
+                                    "geoLevelParentnummer": "261",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 250,
+                                        "neinStimmenAbsolut": 200,
+                                        "eingelegteStimmzettel": 455,
+                                        "anzahlStimmberechtigte": 900
+                                    }
+                                }
+                            ]
+                        },
+                        {
+                            "geoLevelnummer": "6",
+                            "geoLevelname": "Obwalden",
+                            "resultat": {
+                                "gebietAusgezaehlt": true,
+                                "jaStimmenAbsolut": 10,
+                                "neinStimmenAbsolut": 90,
+                                "eingelegteStimmzettel": 102,
+                                "anzahlStimmberechtigte": 200
+                            },
+                            "bezirke": null,
+                            "gemeinden": [
+                                {
+                                    "geoLevelnummer": "1407",
+                                    "geoLevelname": "Sarnen",
+                                    "geoLevelParentnummer": "6",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 10,
+                                        "neinStimmenAbsolut": 90,
+                                        "eingelegteStimmzettel": 102,
+                                        "anzahlStimmberechtigte": 200
+                                    }
+                                }
+                            ],
+                            "zaehlkreise": null
+                        },
+                        {
+                            "geoLevelnummer": "7",
+                            "geoLevelname": "Nidwalden",
+                            "resultat": {
+                                "gebietAusgezaehlt": true,
+                                "jaStimmenAbsolut": 30,
+                                "neinStimmenAbsolut": 70,
+                                "eingelegteStimmzettel": 108,
+                                "anzahlStimmberechtigte": 250
+                            },
+                            "bezirke": null,
+                            "gemeinden": [
+                                {
+                                    "geoLevelnummer": "1509",
+                                    "geoLevelname": "Stans",
+                                    "geoLevelParentnummer": "7",
+                                    "resultat": {
+                                        "gebietAusgezaehlt": true,
+                                        "jaStimmenAbsolut": 30,
+                                        "neinStimmenAbsolut": 70,
+                                        "eingelegteStimmzettel": 108,
+                                        "anzahlStimmberechtigte": 250
+                                    }
+                                }
+                            ],
+                            "zaehlkreise": null
+                        }
+                    ]
+                }
+            ]
+        }
+    }"#;
+
+    const CANTONAL_JSON: &str = r#"{
+        "abstimmtag": "20240922",
+        "timestamp": "2024-09-22T17:12:34.123Z",
+        "kantone": [
+            {
+                "geoLevelnummer": 1,
+                "geoLevelname": "Zürich",
+                "nochKeineInformation": false,
+                "vorlagen": [
+                    {
+                        "vorlagenId": 101,
+                        "reihenfolgeAnzeige": 1,
+                        "vorlagenTitel": [
+                            { "langKey": "de", "text": "Stipendiengesetz" }
+                        ],
+                        "vorlageBeendet": true,
+                        "vorlageAngenommen": true,
+                        "vorlagenArtId": 2,
+                        "hauptvorlagenId": null,
+                        "resultat": {
+                            "gebietAusgezaehlt": true,
+                            "jaStimmenAbsolut": 700,
+                            "neinStimmenAbsolut": 300,
+                            "eingelegteStimmzettel": 1010,
+                            "anzahlStimmberechtigte": 2000
+                        },
+                        "bezirke": null,
+                        "gemeinden": [
+                            {
+                                "geoLevelnummer": "1",
+                                "geoLevelname": "Aeugst am Albis",
+                                "geoLevelParentnummer": "101",
+                                "resultat": {
+                                    "gebietAusgezaehlt": true,
+                                    "jaStimmenAbsolut": 150,
+                                    "neinStimmenAbsolut": 100,
+                                    "eingelegteStimmzettel": 255,
+                                    "anzahlStimmberechtigte": 500
+                                }
+                            },
+                            {
+                                "geoLevelnummer": "261",
+                                "geoLevelname": "Zürich",
+                                "geoLevelParentnummer": "112",
+                                "resultat": {
+                                    "gebietAusgezaehlt": true,
+                                    "jaStimmenAbsolut": 550,
+                                    "neinStimmenAbsolut": 200,
+                                    "eingelegteStimmzettel": 755,
+                                    "anzahlStimmberechtigte": 1500
+                                }
+                            }
+                        ],
+                        "zaehlkreise": null
+                    }
+                ]
+            },
+            {
+                "geoLevelnummer": 2,
+                "geoLevelname": "Bern",
+                "nochKeineInformation": false,
+                "vorlagen": [
+                    {
+                        "vorlagenId": 201,
+                        "reihenfolgeAnzeige": 1,
+                        "vorlagenTitel": [
+                            { "langKey": "de", "text": "Initiative Verkehr" },
+                            { "langKey": "fr", "text": "Initiative trafic" }
+                        ],
+                        "vorlageBeendet": false,
+                        "vorlageAngenommen": false,
+                        "vorlagenArtId": 3,
+                        "hauptvorlagenId": null,
+                        "resultat": {
+                            "gebietAusgezaehlt": false,
+                            "jaStimmenAbsolut": 40,
+                            "neinStimmenAbsolut": 60,
+                            "eingelegteStimmzettel": 101,
+                            "anzahlStimmberechtigte": 400
+                        },
+                        "bezirke": null,
+                        "gemeinden": null,
+                        "zaehlkreise": null
+                    },
+                    {
+                        "vorlagenId": 202,
+                        "reihenfolgeAnzeige": 2,
+                        "vorlagenTitel": [
+                            { "langKey": "de", "text": "Gegenvorschlag Verkehr" },
+                            { "langKey": "fr", "text": "Contre-projet trafic" }
+                        ],
+                        "vorlageBeendet": false,
+                        "vorlageAngenommen": false,
+                        "vorlagenArtId": 4,
+                        "hauptvorlagenId": 201,
+                        "resultat": {
+                            "gebietAusgezaehlt": false,
+                            "jaStimmenAbsolut": 55,
+                            "neinStimmenAbsolut": 45,
+                            "eingelegteStimmzettel": 101,
+                            "anzahlStimmberechtigte": 400
+                        },
+                        "bezirke": null,
+                        "gemeinden": null,
+                        "zaehlkreise": null
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    /// Serves one canned HTTP response per connection on a local port and returns
+    /// the base URL together with the received requests.
+    #[cfg(feature = "net")]
+    fn serve(responses: Vec<String>) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    request.push_str(&line);
+                }
+                let _ = sender.send(request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    #[cfg(feature = "net")]
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {status}\r\nconnection: close\r\n");
+        for header in headers {
+            response.push_str(header);
+            response.push_str("\r\n");
+        }
+        response + &format!("content-length: {}\r\n\r\n{body}", body.len())
+    }
+
+    fn national_data() -> national::Data {
+        national::Data::from_json(NATIONAL_JSON).unwrap()
+    }
+
+    fn cantonal_data() -> cantonal::Data {
+        cantonal::Data::from_json(CANTONAL_JSON).unwrap()
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn dates() {
+        use chrono::{NaiveDate, TimeZone, Utc};
+
+        let day = NaiveDate::from_ymd_opt(2024, 9, 22).unwrap();
+        let generated = Utc.with_ymd_and_hms(2024, 9, 22, 17, 12, 34).unwrap()
+            + chrono::Duration::milliseconds(123);
+        let data = national_data();
+        assert_eq!(data.voting_day(), Ok(day));
+        assert_eq!(data.generated_at(), Ok(generated));
+        let data = cantonal_data();
+        assert_eq!(data.voting_day(), Ok(day));
+        assert_eq!(data.generated_at(), Ok(generated));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn cached_client() {
+        let dir = std::env::temp_dir().join(format!("stimmt-cache-{}", std::process::id()));
+        let cache = CachedClient::new(&dir, Duration::from_secs(60));
+        let url = "https://example.invalid/sd-t-17-02-20240922-eidgAbstimmung.json";
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(cache.cache_path(url), NATIONAL_JSON).unwrap();
+        assert_eq!(cache.national_by_url(url).await.unwrap(), national_data());
+        let paths: HashSet<_> = ["x-y", "x_y", "x.y"]
+            .iter()
+            .map(|name| cache.cache_path(&format!("https://example.invalid/{name}.json")))
+            .collect();
+        assert_eq!(paths.len(), 3);
+        let long = format!("https://example.invalid/{}.json", "a".repeat(500));
+        assert_eq!(cache.cache_path(&long).file_name().unwrap().len(), 37);
+
+        cache.clear().await.unwrap();
+        assert!(!cache.cache_path(url).exists());
+        assert!(cache.national_by_url(url).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        let original: serde_json::Value = serde_json::from_str(NATIONAL_JSON).unwrap();
+        assert_eq!(serde_json::to_value(national_data()).unwrap(), original);
+        let original: serde_json::Value = serde_json::from_str(CANTONAL_JSON).unwrap();
+        assert_eq!(serde_json::to_value(cantonal_data()).unwrap(), original);
+
+        // Missing and empty geo levels and a missing info text are written as `null`.
+        let mut input = original.clone();
+        let issue = &mut input["kantone"][1]["vorlagen"][0];
+        issue.as_object_mut().unwrap().remove("bezirke");
+        issue["gemeinden"] = serde_json::json!([]);
+        let data = cantonal::Data::from_json(&input.to_string()).unwrap();
+        assert_eq!(serde_json::to_value(data).unwrap(), original);
+
+        let original: serde_json::Value = serde_json::from_str(NATIONAL_JSON).unwrap();
+        let mut input = original.clone();
+        let issue = &mut input["schweiz"]["vorlagen"][0];
+        issue.as_object_mut().unwrap().remove("reserveInfoText");
+        let canton = &mut issue["kantone"][1];
+        canton.as_object_mut().unwrap().remove("bezirke");
+        canton["zaehlkreise"] = serde_json::json!([]);
+        let data = national::Data::from_json(&input.to_string()).unwrap();
+        assert_eq!(serde_json::to_value(data).unwrap(), original);
+
+        let mut data = national_data();
+        let mut provisional = data.country.issues[0].clone();
+        provisional.issue_id += 1;
+        provisional.provisional = true;
+        data.country.issues.push(provisional);
+        data.country.issues.retain(|issue| !issue.provisional);
+        assert_eq!(data.country.issues.len(), 1);
+        assert_eq!(serde_json::to_value(&data).unwrap(), original);
+    }
+
+    #[test]
+    fn from_reader() {
+        let data = national::Data::from_reader(NATIONAL_JSON.as_bytes()).unwrap();
+        assert_eq!(data, national_data());
+        let data = cantonal::Data::from_reader(CANTONAL_JSON.as_bytes()).unwrap();
+        assert_eq!(data, cantonal_data());
+        assert!(national::Data::from_json(CANTONAL_JSON).is_err());
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn client_retries() {
+        let (url, _) = serve(vec![
+            response("503 Service Unavailable", &[], ""),
+            response("200 OK", &[], NATIONAL_JSON),
+        ]);
+        let client = Client::builder()
+            .retries(1)
+            .backoff(Duration::from_millis(1))
+            .build()
+            .unwrap();
+        assert_eq!(client.national_by_url(&url).await.unwrap(), national_data());
+
+        let (url, _) = serve(vec![
+            response("404 Not Found", &[], ""),
+            response("200 OK", &[], NATIONAL_JSON),
+        ]);
+        assert!(matches!(
+            client.national_by_url(&url).await,
+            Err(StimmtError::NotFound(_))
+        ));
+
+        let (url, _) = serve(vec![response("503 Service Unavailable", &[], "")]);
+        let result = Client::new().national_by_url(&url).await;
+        assert!(matches!(result, Err(StimmtError::Http(_))));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn cached_fetcher() {
+        let (url, requests) = serve(vec![
+            response("200 OK", &["etag: \"v1\""], NATIONAL_JSON),
+            response("304 Not Modified", &[], ""),
+            response("404 Not Found", &[], ""),
+        ]);
+        let mut fetcher = CachedFetcher::<national::Data>::new(&url);
+        let refresh = fetcher.refresh().await.unwrap();
+        assert_eq!(refresh, Refresh::Refreshed(national_data()));
+        assert_eq!(fetcher.etag(), Some("\"v1\""));
+        assert!(!requests
+            .recv()
+            .unwrap()
+            .to_lowercase()
+            .contains("if-none-match"));
+
+        assert_eq!(fetcher.refresh().await.unwrap(), Refresh::Unchanged);
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("if-none-match: \"v1\"\r\n"));
+        assert_eq!(fetcher.data(), Some(&national_data()));
+
+        let result = fetcher.refresh().await;
+        assert!(matches!(result, Err(StimmtError::NotFound(_))));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn client_headers_and_proxy() {
+        let (url, requests) = serve(vec![response("200 OK", &[], NATIONAL_JSON)]);
+        let client = Client::builder()
+            .user_agent("stimmt-test")
+            .header(
+                header::HeaderName::from_static("x-contact"),
+                header::HeaderValue::from_static("ops@example.com"),
+            )
+            .proxy(url)
+            .build()
+            .unwrap();
+        let data = client
+            .national_by_url("http://example.invalid/national.json")
+            .await
+            .unwrap();
+        assert_eq!(data, national_data());
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.starts_with("get http://example.invalid/national.json "));
+        assert!(request.contains("user-agent: stimmt-test\r\n"));
+        assert!(request.contains("x-contact: ops@example.com\r\n"));
+
+        let result = Client::builder().proxy("not a url").build();
+        assert!(matches!(result, Err(StimmtError::Http(_))));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn poll_changes() {
+        use futures_util::StreamExt;
+
+        let updated = NATIONAL_JSON.replace("2024-09-22T17:12:34.123Z", "2024-09-22T17:30:00Z");
+        let (url, _) = serve(vec![
+            response("200 OK", &[], NATIONAL_JSON),
+            response("200 OK", &[], NATIONAL_JSON),
+            response("503 Service Unavailable", &[], ""),
+            response("200 OK", &[], &updated),
+        ]);
+        let stream = super::poll_changes(
+            Client::new(),
+            Duration::from_millis(1),
+            move |client| {
+                let url = url.clone();
+                async move { client.national_by_url(&url).await }
+            },
+            |data: &national::Data| &data.timestamp,
+        );
+        let mut stream = std::pin::pin!(stream);
+        assert_eq!(stream.next().await.unwrap().unwrap(), national_data());
+        assert!(stream.next().await.unwrap().is_err());
+        let data = stream.next().await.unwrap().unwrap();
+        assert_eq!(data.timestamp, "2024-09-22T17:30:00Z");
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn accept_encoding() {
+        let (url, requests) = serve(vec![response("200 OK", &[], NATIONAL_JSON)]);
+        Client::new().national_by_url(&url).await.unwrap();
+        let request = requests.recv().unwrap().to_lowercase();
+        let accept_encoding = request
+            .lines()
+            .find_map(|line| line.strip_prefix("accept-encoding:"))
+            .unwrap();
+        assert!(accept_encoding.contains("gzip"));
+        assert!(accept_encoding.contains("br"));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test(start_paused = true)]
+    async fn rate_limiter() {
+        let rate_limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        for _ in 0..6 {
+            rate_limiter.clone().acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+
+    #[cfg(feature = "net")]
+    #[tokio::test]
+    async fn client_rate_limiter() {
+        let (url, _) = serve(vec![
+            response("200 OK", &[], NATIONAL_JSON),
+            response("200 OK", &[], NATIONAL_JSON),
+        ]);
+        let client = Client::builder()
+            .rate_limiter(RateLimiter::new(1))
+            .build()
+            .unwrap();
+        let start = Instant::now();
+        client.national_by_url(&url).await.unwrap();
+        client.national_by_url(&url).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(900));
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn latest_coverage() {
+        let response = r#"{
+            "result": {
+                "resources": [
+                    { "coverage": "2024-9-22", "url": "september" },
+                    { "coverage": "2024-11-24", "url": "november" },
+                    { "coverage": "9.2.2025", "url": "february" },
+                    { "coverage": "2024-06-09", "url": "june" },
+                    { "coverage": "unknown", "url": "unknown" }
+                ]
+            }
+        }"#;
+        let resources = parse_resources(response).unwrap();
+        assert_eq!(resources.len(), 5);
+        assert_eq!(resources[0].coverage, "2024-9-22");
+        assert_eq!(resources[0].url, "september");
+        assert_eq!(latest_resource_url(resources).unwrap(), "february");
+        assert_eq!(parse_coverage("20240922"), Some((2024, 9, 22)));
+        assert_eq!(parse_coverage("2024-13-01"), None);
+
+        let response = r#"{ "result": { "resources": [] } }"#;
+        let resources = parse_resources(response).unwrap();
+        assert!(matches!(
+            latest_resource_url(resources),
+            Err(StimmtError::NoResources)
+        ));
+        assert!(matches!(parse_resources("{}"), Err(StimmtError::Parse(_))));
+
+        let response = r#"{
+            "help": "https://ckan.opendata.swiss/api/3/action/help_show?name=package_show",
+            "success": false,
+            "error": { "__type": "Not Found Error", "message": "Not found" }
+        }"#;
+        assert!(matches!(
+            parse_resources(response),
+            Err(StimmtError::Ckan(message)) if message == "Not Found Error: Not found"
+        ));
+        let response = r#"{ "success": false }"#;
+        assert!(matches!(
+            parse_resources(response),
+            Err(StimmtError::Ckan(_))
+        ));
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn coverage_lookup() {
+        let resources = vec![
+            ResourceInfo {
+                coverage: "2024-09-22".into(),
+                url: "provisional".into(),
+            },
+            ResourceInfo {
+                coverage: "2024-09-22 final".into(),
+                url: "final".into(),
+            },
+        ];
+        let url = coverage_resource_url(resources.clone(), "2024-09-22 final").unwrap();
+        assert_eq!(url, "final");
+        assert!(matches!(
+            coverage_resource_url(resources, "2024-11-24"),
+            Err(StimmtError::CoverageNotFound(coverage)) if coverage == "2024-11-24"
+        ));
+    }
+
+    #[test]
+    fn url_for_date() {
+        assert_eq!(
+            national::url_for_date(2024, 9, 22),
+            "https://ogd-static.voteinfo-app.ch/v1/ogd/sd-t-17-02-20240922-eidgAbstimmung.json"
+        );
+        assert_eq!(
+            cantonal::url_for_date(2024, 9, 22),
+            "https://ogd-static.voteinfo-app.ch/v1/ogd/sd-t-17-02-20240922-kantAbstimmung.json"
+        );
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn static_url_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19988), (2024, 9, 22));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(last_sunday(19988), 19988);
+        assert_eq!(last_sunday(19994), 19988);
+        assert_eq!(last_sunday(19995), 19995);
+    }
+
+    #[test]
+    fn issue_type() {
+        for id in 0..8 {
+            assert_eq!(IssueType::from_id(id).id(), id);
+        }
+        assert_eq!(IssueType::from_id(9), IssueType::Unknown(9));
+        assert_eq!(
+            IssueType::PopularInitiative.to_string(),
+            "popular initiative"
+        );
+        let data = national_data();
+        assert_eq!(
+            data.country.issues[0].issue_type(),
+            IssueType::PopularInitiative
+        );
+        let data = cantonal_data();
+        let bern = &data.kantone[1];
+        assert_eq!(bern.issues[1].issue_type(), IssueType::CounterProposal);
+    }
+
+    #[test]
+    fn lang_round_trip() {
+        for lang in Lang::ALL {
+            assert_eq!(lang.to_string().parse(), Ok(lang));
+            assert_eq!(lang.to_string(), lang.iso_639_1());
+        }
+        assert_eq!("DE".parse(), Ok(Lang::DE));
+        assert_eq!(Lang::RM.to_string(), "rm");
+        assert!("gsw".parse::<Lang>().is_err());
+        assert!(Lang::ALL.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn canton_identity() {
+        for (i, canton) in Canton::ALL.into_iter().enumerate() {
+            assert_eq!(canton.number() as usize, i + 1);
+            assert_eq!(Canton::from_number(canton.number()), Some(canton));
+            assert_eq!(
+                Canton::from_abbreviation(canton.abbreviation()),
+                Some(canton)
+            );
+        }
+        assert_eq!(Canton::from_number(0), None);
+        assert_eq!(Canton::from_number(27), None);
+        assert_eq!(Canton::GE.abbreviation(), "GE");
+        assert_eq!(Canton::GE.name(Lang::DE), "Genf");
+        assert_eq!(Canton::GE.name(Lang::FR), "Genève");
+        assert_eq!(Canton::from_abbreviation("zh"), Some(Canton::ZH));
+
+        let data = national_data();
+        assert_eq!(data.country.issues[0].cantons[1].canton(), Some(Canton::OW));
+        let data = cantonal_data();
+        assert_eq!(data.kantone[1].canton(), Some(Canton::BE));
+    }
+
+    #[test]
+    fn outcome_flat() {
+        let json = r#"{
+            "gebietAusgezaehlt": true,
+            "jaStimmenAbsolut": 10,
+            "neinStimmenAbsolut": 20,
+            "eingelegteStimmzettel": 31,
+            "anzahlStimmberechtigte": 60
+        }"#;
+        let outcome: Outcome = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome {
+                count_completed: true,
+                yes_votes: 10,
+                no_votes: 20,
+                cast_ballot_papers: 31,
+                eligible_voters: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn outcome_nested() {
+        let json = r#"{
+            "gebietAusgezaehlt": false,
+            "zahlen": {
+                "jaStimmenAbsolut": 10,
+                "neinStimmenAbsolut": 20,
+                "eingelegteStimmzettel": 31,
+                "anzahlStimmberechtigte": 60
+            }
+        }"#;
+        let outcome: Outcome = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome {
+                count_completed: false,
+                yes_votes: 10,
+                no_votes: 20,
+                cast_ballot_papers: 31,
+                eligible_voters: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn ratios_without_votes() {
+        let mut outcome = Outcome {
+            count_completed: false,
+            yes_votes: 0,
+            no_votes: 0,
+            cast_ballot_papers: 0,
+            eligible_voters: 0,
+        };
+        assert_eq!(outcome.yes_ratio(), None);
+        assert_eq!(outcome.no_ratio(), None);
+        assert_eq!(outcome.valid_votes_ratio(), None);
+        assert_eq!(outcome.invalid_votes_ratio(), None);
+        assert_eq!(outcome.turnout(), None);
+
+        outcome.yes_votes = 0;
+        outcome.no_votes = 4;
+        outcome.cast_ballot_papers = 5;
+        outcome.eligible_voters = 10;
+        assert_eq!(outcome.yes_ratio(), Some(0.0));
+        assert_eq!(outcome.no_ratio(), Some(1.0));
+        assert_eq!(outcome.valid_votes_ratio(), Some(0.8));
+        assert_eq!(outcome.turnout(), Some(0.4));
+    }
+
+    #[test]
+    fn outcome_builder() {
+        let outcome = Outcome::builder().yes_votes(30).no_votes(20).build();
+        assert_eq!(outcome, Outcome::new(30, 20, 50, 50, false));
+        let outcome = Outcome::builder()
+            .no_votes(20)
+            .cast_ballot_papers(25)
+            .eligible_voters(100)
+            .count_completed(true)
+            .build();
+        assert_eq!(outcome.invalid_votes(), 5);
+        assert_eq!(outcome.abstentions(), 75);
+        assert!(outcome.count_completed);
+    }
+
+    #[test]
+    fn margin() {
+        let mut outcome = Outcome {
+            count_completed: true,
+            yes_votes: 0,
+            no_votes: 0,
+            cast_ballot_papers: 0,
+            eligible_voters: 0,
+        };
+        assert_eq!(outcome.margin(), 0);
+        assert_eq!(outcome.margin_ratio(), None);
+        assert_eq!(outcome.margin_over_electorate(), None);
+        assert!(!outcome.is_close(1.0));
+
+        outcome.yes_votes = 48;
+        outcome.no_votes = 52;
+        assert_eq!(outcome.margin(), -4);
+        assert_eq!(outcome.margin_ratio(), Some(-0.04));
+        assert!(outcome.is_close(0.05));
+        assert!(!outcome.is_close(0.01));
+        assert_eq!(outcome.margin_over_electorate(), None);
+
+        outcome.eligible_voters = 200;
+        assert_eq!(outcome.margin_over_electorate(), Some(-0.02));
+    }
+
+    #[test]
+    fn inconsistent_outcome() {
+        let mut outcome = Outcome {
+            count_completed: true,
+            yes_votes: 10,
+            no_votes: 20,
+            cast_ballot_papers: 31,
+            eligible_voters: 60,
+        };
+        assert!(outcome.is_consistent());
+        assert_eq!(outcome.invalid_votes(), 1);
+        outcome.cast_ballot_papers = 29;
+        assert!(!outcome.is_consistent());
+        assert_eq!(outcome.invalid_votes(), 0);
+    }
+
+    #[test]
+    fn sum_outcomes() {
+        let data = national_data();
+        let issue = &data.country.issues[0];
+        let sum: Outcome = issue.all_communes().map(|(_, c)| c.outcome).sum();
+        assert_eq!(sum, issue.outcome);
+
+        let mut pending = issue.outcome;
+        pending.count_completed = false;
+        assert!(!(issue.outcome + pending).count_completed);
+        assert_eq!((issue.outcome + pending).yes_votes, 1280);
+        assert!(
+            std::iter::empty::<Outcome>()
+                .sum::<Outcome>()
+                .count_completed
+        );
+    }
+
+    #[test]
+    fn abstentions() {
+        let mut outcome = Outcome {
+            count_completed: true,
+            yes_votes: 10,
+            no_votes: 20,
+            cast_ballot_papers: 31,
+            eligible_voters: 62,
+        };
+        assert_eq!(outcome.abstentions(), 31);
+        assert_eq!(outcome.abstention_ratio(), Some(0.5));
+        outcome.cast_ballot_papers = 70;
+        assert_eq!(outcome.abstentions(), 0);
+        outcome.eligible_voters = 0;
+        assert_eq!(outcome.abstention_ratio(), None);
+    }
+
+    #[test]
+    fn outcome_missing_field() {
+        let json = r#"{ "gebietAusgezaehlt": true, "zahlen": { "jaStimmenAbsolut": 10 } }"#;
+        assert!(serde_json::from_str::<Outcome>(json).is_err());
+    }
+
+    #[test]
+    fn national_turnout() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        let communes: u32 = issue
+            .all_communes()
+            .map(|(_, commune)| commune.outcome.eligible_voters)
+            .sum();
+        assert_eq!(issue.total_eligible_voters(), communes);
+        assert_eq!(issue.national_turnout(), issue.computed_outcome().turnout());
+
+        let zurich = &mut issue.cantons[0];
+        let zurich_communes = zurich.sub_level_outcome().unwrap().eligible_voters;
+        zurich.communes = None;
+        zurich.districts = None;
+        let zurich = zurich.outcome.eligible_voters;
+        assert_eq!(
+            issue.total_eligible_voters(),
+            communes - zurich_communes + zurich
+        );
+    }
+
+    #[test]
+    fn outcomes_at() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        let count = |issue: &national::Issue, level| issue.outcomes_at(level).count();
+        assert_eq!(count(issue, GeoLevel::Country), 1);
+        assert_eq!(count(issue, GeoLevel::Canton), issue.cantons.len());
+        assert_eq!(
+            count(issue, GeoLevel::Commune),
+            issue.all_communes().count()
+        );
+        assert_eq!(count(issue, GeoLevel::Constituency), 2);
+        let communes: Outcome = issue.outcomes_at(GeoLevel::Commune).copied().sum();
+        assert_eq!(
+            communes,
+            issue
+                .all_communes()
+                .map(|(_, commune)| commune.outcome)
+                .sum()
+        );
+
+        for canton in &mut issue.cantons {
+            canton.districts = None;
+        }
+        assert_eq!(count(issue, GeoLevel::District), 0);
+    }
+
+    #[test]
+    fn constituency_from_commune() {
+        let data = national_data();
+        let zurich = &data.country.issues[0].cantons[0];
+        let constituency = &zurich.constituencies.as_ref().unwrap()[0];
+        let commune = Commune {
+            geo_levelnumber: constituency.geo_levelnumber.clone(),
+            geo_levelname: constituency.geo_levelname.clone(),
+            geo_level_parentnumber: constituency.geo_level_parentnumber.clone(),
+            outcome: constituency.outcome,
+        };
+        assert_eq!(&Constituency::from(commune), constituency);
+    }
+
+    #[test]
+    fn cantons_by_turnout() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        issue.cantons[0].outcome.count_completed = false;
+        issue.cantons[1].outcome.eligible_voters = 0;
+        assert_eq!(issue.cantons_by_turnout().len(), 1);
+
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        for canton in &mut issue.cantons {
+            canton.outcome.count_completed = true;
+        }
+        let ranking = issue.cantons_by_turnout();
+        assert_eq!(ranking.len(), issue.cantons.len());
+        assert!(ranking.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(issue.highest_turnout_canton(), ranking.first().copied());
+        assert_eq!(issue.lowest_turnout_canton(), ranking.last().copied());
+    }
+
+    #[test]
+    fn annotation() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        issue.provisional = false;
+        for text in [None, Some(""), Some(" \n")] {
+            issue.reserve_info_text = text.map(str::to_string);
+            assert_eq!(issue.annotation(), None);
+            assert!(!issue.needs_attention());
+        }
+        issue.reserve_info_text = Some(" Nachzählung in Stans \n".into());
+        assert_eq!(issue.annotation(), Some("Nachzählung in Stans"));
+        assert!(issue.has_annotation());
+        assert!(issue.needs_attention());
+        issue.reserve_info_text = None;
+        issue.provisional = true;
+        assert!(issue.needs_attention());
+    }
+
+    #[test]
+    fn canton_split() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        issue.cantons[0].outcome.count_completed = false;
+        issue.cantons[1].outcome.yes_votes = 90;
+        issue.cantons[1].outcome.no_votes = 10;
+        issue.cantons[2].outcome.yes_votes = issue.cantons[2].outcome.no_votes;
+        let (accepting, rejecting, pending) = issue.canton_split();
+        let numbers = |cantons: Vec<&national::Canton>| {
+            cantons
+                .iter()
+                .map(|canton| canton.geo_levelnumber.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(accepting), ["6"]);
+        assert_eq!(numbers(rejecting), ["7"]);
+        assert_eq!(numbers(pending), ["1"]);
+        assert_eq!(numbers(issue.cantons_accepting()), ["6"]);
+        assert_eq!(numbers(issue.cantons_rejecting()), ["7"]);
+    }
+
+    #[test]
+    fn pivotal_cantons() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        // ZH (1.0) yes out of a total weight of 2.0: a tie, so only a flip of
+        // either half canton would reach the majority.
+        let pivotal: Vec<_> = issue
+            .pivotal_cantons()
+            .iter()
+            .map(|c| c.geo_levelnumber.as_str())
+            .collect();
+        assert_eq!(pivotal, ["6", "7"]);
+
+        // With cantons still missing, no single flip reaches half the total weight.
+        issue.outcome_cantons.full_canton_count = 20;
+        assert!(issue.pivotal_cantons().is_empty());
+
+        issue.double_majority = false;
+        assert!(issue.pivotal_cantons().is_empty());
+    }
+
+    #[test]
+    fn canton_tree() {
+        let data = national_data();
+        let cantons = &data.country.issues[0].cantons;
+
+        let zurich = cantons[0].as_tree();
+        let districts: Vec<_> = zurich
+            .children
+            .iter()
+            .map(|d| d.geo_levelnumber.as_str())
+            .collect();
+        assert_eq!(districts, ["101", "112"]);
+        let city = &zurich.children[1].children[0];
+        assert_eq!(city.geo_levelname, "Zürich");
+        assert_eq!(city.children.len(), 2);
+
+        let obwalden = cantons[1].as_tree();
+        assert_eq!(obwalden.children.len(), 1);
+        assert_eq!(obwalden.children[0].geo_levelname, "Sarnen");
+        assert!(obwalden.children[0].children.is_empty());
+    }
+
+    #[test]
+    fn all_communes() {
+        let data = national_data();
+        let issue = &data.country.issues[0];
+        let communes: Vec<_> = issue
+            .all_communes()
+            .map(|(canton, commune)| {
+                (
+                    canton.geo_levelnumber.as_str(),
+                    commune.geo_levelname.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            communes,
+            [
+                ("1", "Aeugst am Albis"),
+                ("1", "Zürich"),
+                ("6", "Sarnen"),
+                ("7", "Stans")
+            ]
+        );
+        assert_eq!(issue.all_districts().count(), 2);
+    }
+
+    #[test]
+    fn canton_of_commune() {
+        let data = national_data();
+        let issue = &data.country.issues[0];
+        let index = issue.commune_index();
+        assert_eq!(index.len(), issue.all_communes().count());
+        let stans = index["1509"];
+        assert_eq!(stans.geo_levelname, "Stans");
+        let canton = issue.canton_of_commune(stans).unwrap();
+        assert_eq!(canton.geo_levelnumber, "7");
+
+        let mut stans = stans.clone();
+        stans.geo_level_parentnumber = "99".to_string();
+        assert!(issue.canton_of_commune(&stans).is_none());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn to_csv() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        issue.cantons[2].communes.as_mut().unwrap()[0].geo_levelname = "Stans, \"NW\"".into();
+        let mut buffer = [0; 1024];
+        let mut sink = &mut buffer[..];
+        issue.to_csv(Lang::EN, &mut sink).unwrap();
+        let written = 1024 - sink.len();
+
+        let mut reader = csv::Reader::from_reader(&buffer[..written]);
+        assert_eq!(reader.headers().unwrap().len(), 11);
+        let rows: Vec<_> = reader.records().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), issue.all_communes().count());
+        let stans = rows.iter().find(|row| &row[1] == "1509").unwrap();
+        assert_eq!(&stans[0], issue.cantons[2].geo_levelname);
+        assert_eq!(&stans[2], "Stans, \"NW\"");
+
+        let mut sink = &mut buffer[..100];
+        assert!(issue.to_csv(Lang::DE, &mut sink).is_err());
+
+        let data = cantonal_data();
+        let canton = &data.kantone[0];
+        let mut output = Vec::new();
+        canton.issues[0]
+            .to_csv(canton, Lang::FR, &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Canton,Numéro de commune,"));
+        assert_eq!(output.lines().count(), 3);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn csv_flushes() {
+        /// Records how many bytes had been written at every flush.
+        #[derive(Default)]
+        struct Sink {
+            written: Vec<u8>,
+            flushes: Vec<usize>,
+        }
+
+        impl Write for Sink {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.written.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.flushes.push(self.written.len());
+                Ok(())
+            }
+        }
+
+        let data = national_data();
+        let issue = &data.country.issues[0];
+        let mut sink = Sink::default();
+        issue.to_csv(Lang::DE, &mut sink).unwrap();
+        // The csv writer flushes once more when it is dropped, without new bytes.
+        sink.flushes.dedup();
+        assert_eq!(sink.flushes.len(), issue.all_communes().count() + 1);
+        assert!(sink.flushes.iter().all(|&at| sink.written[at - 1] == b'\n'));
+        assert_eq!(sink.flushes.last(), Some(&sink.written.len()));
+    }
+
+    #[test]
+    fn commune_results() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        let results = issue.commune_results(false).unwrap();
+        assert_eq!(results.len(), issue.all_communes().count());
+        assert_eq!(results[&1509].name, "Stans");
+        assert!(serde_json::to_string(&results).is_ok());
+
+        issue.cantons[2].communes.as_mut().unwrap()[0].geo_levelnumber = "NW-1".into();
+        assert!(matches!(
+            issue.commune_results(false),
+            Err(StimmtError::InvalidGeoLevel(number)) if number == "NW-1"
+        ));
+        assert_eq!(
+            issue.commune_results(true).unwrap().len(),
+            results.len() - 1
+        );
+
+        let data = cantonal_data();
+        let results = data.kantone[0].issues[0].commune_results(false).unwrap();
+        assert_eq!(results[&1].name, "Aeugst am Albis");
+    }
+
+    #[test]
+    fn empty_geo_levels() {
+        let canton = |geo_levels: &str| {
+            let json = format!(
+                r#"{{
+                    "geoLevelnummer": "7",
+                    "geoLevelname": "Nidwalden",
+                    "resultat": {{
+                        "gebietAusgezaehlt": true,
+                        "jaStimmenAbsolut": 1,
+                        "neinStimmenAbsolut": 2,
+                        "eingelegteStimmzettel": 3,
+                        "anzahlStimmberechtigte": 4
+                    }}
+                    {geo_levels}
+                }}"#
+            );
+            serde_json::from_str::<national::Canton>(&json).unwrap()
+        };
+        let missing = canton("");
+        let null = canton(r#", "bezirke": null, "gemeinden": null, "zaehlkreise": null"#);
+        let empty = canton(r#", "bezirke": [], "gemeinden": [], "zaehlkreise": []"#);
+        assert_eq!(missing.communes, None);
+        assert_eq!(missing, null);
+        assert_eq!(missing, empty);
+        assert_eq!(empty.geo_levels(), GeoLevelKind::None);
+
+        let data = national_data();
+        let cantons = &data.country.issues[0].cantons;
+        assert_eq!(cantons[0].geo_levels(), GeoLevelKind::Constituencies);
+        assert_eq!(cantons[1].geo_levels(), GeoLevelKind::Communes);
+        let data = cantonal_data();
+        assert_eq!(
+            data.kantone[0].issues[0].geo_levels(),
+            GeoLevelKind::Communes
+        );
+    }
+
+    #[test]
+    fn status_summary() {
+        let mut data = national_data();
+        let summary = data.status_summary();
+        assert_eq!(summary.completed_issues + summary.pending_issues, 1);
+        data.country.issues[0].issue_completed = true;
+        data.country.issues[0].provisional = true;
+        let summary = data.status_summary();
+        assert_eq!(summary.completed_issues, 1);
+        assert!(summary.any_provisional);
+        assert!(!summary.is_final());
+        data.country.issues[0].provisional = false;
+        assert_eq!(data.status_summary().is_final(), !data.country.no_infos_yet);
+
+        let mut data = cantonal_data();
+        let issues: usize = data.kantone.iter().map(|canton| canton.issues.len()).sum();
+        let summary = data.status_summary();
+        assert_eq!(summary.completed_issues + summary.pending_issues, issues);
+        for canton in &mut data.kantone {
+            canton.no_infos_yet = true;
+        }
+        assert!(data.status_summary().no_infos_yet);
+    }
+
+    #[test]
+    fn counting_progress() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        assert_eq!(issue.counting_progress(), 1.0);
+        issue.cantons[0].communes.as_mut().unwrap()[1]
+            .outcome
+            .count_completed = false;
+        assert_eq!(issue.completed_communes().count(), 3);
+        assert_eq!(issue.counting_progress(), 0.75);
+
+        for canton in &mut issue.cantons {
+            canton.communes = None;
+        }
+        issue.cantons[2].outcome.count_completed = false;
+        assert_eq!(issue.counting_progress(), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn communes_above_turnout() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        assert_eq!(issue.communes_above_turnout(0.45), (3, 4));
+        issue.cantons[1].communes.as_mut().unwrap()[0]
+            .outcome
+            .count_completed = false;
+        assert_eq!(issue.communes_above_turnout(0.45), (2, 3));
+    }
+
+    #[test]
+    fn available_languages() {
+        let data = national_data();
+        let issue = &data.country.issues[0];
+        assert_eq!(
+            issue.available_languages(),
+            [Lang::DE, Lang::FR, Lang::IT, Lang::EN]
+        );
+        assert_eq!(issue.get_title(Lang::RM), None);
+    }
+
+    #[test]
+    fn duplicate_commune_numbers() {
+        let mut data = national_data();
+        assert!(data.duplicate_commune_numbers().is_empty());
+        let cantons = &mut data.country.issues[0].cantons;
+        let sarnen = cantons[1].communes.as_ref().unwrap()[0].clone();
+        cantons[2].communes.as_mut().unwrap().push(sarnen);
+        assert_eq!(data.duplicate_commune_numbers(), ["1407"]);
+    }
+
+    #[test]
+    fn project_with_baselines() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        let baselines = HashMap::from([("1509".to_string(), 0.5)]);
+        assert_eq!(
+            issue.project_with_baselines(&baselines),
+            Some(640.0 / 1200.0)
+        );
+
+        for canton in &mut issue.cantons {
+            for commune in canton.communes.iter_mut().flatten() {
+                commune.outcome.count_completed = commune.geo_levelnumber == "1407";
+            }
+        }
+        // Only Sarnen is counted (10 of 100 valid votes yes, turnout 0.5), so Stans
+        // projects to 125 valid votes at its baseline and the rest at 10 %.
+        let expected = (10.0 + 125.0 * 0.5 + 1000.0 * 0.1) / (100.0 + 125.0 + 1000.0);
+        assert_eq!(issue.project_with_baselines(&baselines), Some(expected));
+
+        for commune in issue.cantons[1].communes.iter_mut().flatten() {
+            commune.outcome.count_completed = false;
+        }
+        assert_eq!(issue.project_with_baselines(&baselines), None);
+    }
+
+    #[test]
+    fn title_fallback() {
+        let data = national_data();
+        let issue = &data.country.issues[0];
+        assert_eq!(
+            issue.get_title_or_default(Lang::RM),
+            Some("Biodiversitätsinitiative")
+        );
+        assert_eq!(
+            issue.get_title_with_fallback(&[Lang::RM, Lang::IT]),
+            Some("Iniziativa biodiversità")
+        );
+        assert_eq!(issue.get_title_with_fallback(&[Lang::RM]), None);
+
+        let data = cantonal_data();
+        let issue = &data.kantone[0].issues[0];
+        assert_eq!(
+            issue.get_title_or_default(Lang::FR),
+            Some("Stipendiengesetz")
+        );
+    }
+
+    #[test]
+    fn find_issues_by_title() {
+        let data = national_data();
+        assert_eq!(
+            data.find_issues_by_title("BIODIVERSITÄT", Some(Lang::DE))
+                .len(),
+            1
+        );
+        assert!(data
+            .find_issues_by_title("biodiversità", Some(Lang::DE))
+            .is_empty());
+        assert_eq!(data.find_issues_by_title("biodiversità", None).len(), 1);
+        assert!(data.find_issues_by_title(" ", Some(Lang::RM)).is_empty());
+
+        let data = cantonal_data();
+        let issues = data.find_issues_by_title("verkehr", None);
+        assert_eq!(issues.len(), 2);
+        assert!(issues
+            .iter()
+            .all(|(canton, _)| canton.geo_levelname == "Bern"));
+        assert_eq!(data.find_issues_by_title("trafic", Some(Lang::FR)).len(), 2);
+    }
+
+    #[test]
+    fn title_index() {
+        let data = national_data();
+        let issue = &data.country.issues[0];
+        let index = issue.title_index();
+        for lang in Lang::ALL {
+            assert_eq!(index.get(lang), issue.get_title(lang));
+        }
+    }
+
+    #[test]
+    fn aggregation_lag() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        assert!(issue.aggregation_lag().is_zero());
+        issue.cantons[0].outcome.yes_votes -= 50;
+        issue.cantons[0].outcome.cast_ballot_papers -= 50;
+        assert_eq!(
+            issue.aggregation_lag(),
+            OutcomeDelta {
+                yes_votes: 50,
+                no_votes: 0,
+                cast_ballot_papers: 50,
+                eligible_voters: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn canton_vs_national() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        assert_eq!(issue.canton_vs_national(6), Some(0.1 - 640.0 / 1200.0));
+        assert_eq!(issue.canton_vs_national(2), None);
+        issue.cantons[1].outcome.count_completed = false;
+        assert_eq!(issue.canton_vs_national(6), None);
+    }
+
+    #[test]
+    fn canton_invalid_rates() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        let rates: Vec<_> = issue
+            .canton_invalid_rates()
+            .into_iter()
+            .map(|(canton, rate)| (canton.geo_levelnumber.as_str(), rate))
+            .collect();
+        assert_eq!(
+            rates,
+            [
+                ("1", Some(20.0 / 1020.0)),
+                ("6", Some(2.0 / 102.0)),
+                ("7", Some(8.0 / 108.0)),
+            ]
+        );
+
+        issue.cantons[1].outcome.cast_ballot_papers = 0;
+        assert_eq!(issue.canton_invalid_rates()[1].1, None);
+    }
+
+    #[test]
+    fn validate() {
+        let mut data = national_data();
+        let report = data.validate();
+        assert!(report.reconciliation_failures.is_empty());
+        assert!(report.zero_electorate_communes.is_empty());
+        assert!(report.inconsistent_accepted_issues.is_empty());
+        assert_eq!(report.missing_cantons[&6760].len(), 23);
+
+        let issue = &mut data.country.issues[0];
+        issue.issue_accepted = true;
+        let stans = &mut issue.cantons[2].communes.as_mut().unwrap()[0];
+        stans.outcome.eligible_voters = 0;
+        let report = data.validate();
+        assert_eq!(report.reconciliation_failures.len(), 1);
+        assert_eq!(report.reconciliation_failures[0].canton, "7");
+        assert_eq!(report.reconciliation_failures[0].delta.eligible_voters, 250);
+        assert_eq!(report.zero_electorate_communes, ["1509"]);
+        assert_eq!(report.inconsistent_accepted_issues, [6760]);
+        assert!(!report.is_valid());
+        assert!(serde_json::to_string(&report).is_ok());
+    }
+
+    #[test]
+    fn reconcile() {
+        use national::{OutcomeField, Reconciliation};
+
+        let mut data = national_data();
+        let canton = &mut data.country.issues[0].cantons[2];
+        assert_eq!(canton.reconcile(), Reconciliation::Consistent);
+
+        canton.communes.as_mut().unwrap()[0].outcome.no_votes += 3;
+        let Reconciliation::Mismatch {
+            field,
+            expected,
+            actual,
+        } = canton.reconcile()
+        else {
+            panic!("expected a mismatch");
+        };
+        assert_eq!(field, OutcomeField::NoVotes);
+        assert_eq!(actual, expected + 3);
+        let failures = data.reconciliation_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].delta.no_votes, -3);
+
+        let canton = &mut data.country.issues[0].cantons[2];
+        canton.communes = None;
+        canton.districts = None;
+        assert_eq!(canton.reconcile(), Reconciliation::NoSubLevels);
+    }
+
+    #[test]
+    fn computed_outcome() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        assert_eq!(issue.computed_outcome(), issue.outcome);
+        assert!(issue.aggregate_mismatch().is_zero());
+
+        // Without communes, Zürich falls back to its districts, Obwalden to itself.
+        issue.cantons[0].communes = None;
+        issue.cantons[0].districts.as_mut().unwrap()[0]
+            .outcome
+            .no_votes += 5;
+        issue.cantons[1].communes = None;
+        assert_eq!(issue.aggregate_mismatch().no_votes, -5);
+    }
+
+    #[test]
+    fn support_weighted_by_canton() {
+        let data = national_data();
+        let support = data.country.issues[0].support_weighted_by_canton();
+        assert_eq!(
+            support,
+            vec![(1, 600.0 / 640.0), (6, 10.0 / 640.0), (7, 30.0 / 640.0)]
+        );
+    }
+
+    #[test]
+    fn canton_majority() {
+        let mut cantons = national::OutcomeCantons {
+            yes_full_cantons: 10,
+            no_full_cantons: 8,
+            full_canton_count: 20,
+            yes_half_cantons: 3,
+            no_half_cantons: 1,
+            half_canton_count: 6,
+        };
+        assert_eq!(cantons.yes_canton_weight(), 11.5);
+        assert_eq!(cantons.no_canton_weight(), 8.5);
+        assert_eq!(cantons.total_canton_weight(), 23.0);
+        assert!(!cantons.cantonal_majority_reached());
+        assert!(!cantons.cantonal_majority_decided());
+
+        cantons.no_full_cantons = 10;
+        cantons.no_half_cantons = 3;
+        assert!(!cantons.cantonal_majority_reached());
+        assert!(cantons.cantonal_majority_decided());
+
+        cantons.yes_full_cantons = 11;
+        cantons.no_full_cantons = 9;
+        assert!(cantons.cantonal_majority_reached());
+        assert!(cantons.cantonal_majority_decided());
+    }
+
+    #[test]
+    fn passes() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        // More yes than no votes, but 1 against 1 cantons.
+        assert!(issue.requires_canton_majority());
+        assert!(!issue.passes());
+        issue.double_majority = false;
+        assert!(!issue.requires_canton_majority());
+        assert!(issue.passes());
+        issue.outcome.no_votes = issue.outcome.yes_votes;
+        assert!(!issue.passes());
+
+        let data = cantonal_data();
+        let zurich = &data.kantone[0].issues[0];
+        assert!(!zurich.requires_canton_majority());
+        assert!(zurich.passes());
+        assert!(!data.kantone[1].issues[0].passes());
+        assert!(data.kantone[1].issues[1].passes());
+    }
+
+    #[test]
+    fn issue_lookup() {
+        let data = national_data();
+        assert_eq!(data.country.issue(6760).unwrap().display_order, 1);
+        assert!(data.country.issue(1).is_none());
+        assert_eq!(data.country.main_issues().count(), 1);
+        assert_eq!(data.country.sub_issues_of(6760).count(), 0);
+
+        let data = cantonal_data();
+        let bern = &data.kantone[1];
+        assert_eq!(bern.issue(202).unwrap().display_order, 2);
+        let main: Vec<_> = bern.main_issues().map(|issue| issue.issue_id).collect();
+        assert_eq!(main, [201]);
+        let sub: Vec<_> = bern
+            .sub_issues_of(201)
+            .map(|issue| issue.issue_id)
+            .collect();
+        assert_eq!(sub, [202]);
+    }
+
+    #[test]
+    fn is_accepted_computed() {
+        let mut data = national_data();
+        let issue = &mut data.country.issues[0];
+        assert_eq!(issue.is_accepted_computed(), Some(false));
+        issue.outcome_cantons.yes_half_cantons = 2;
+        issue.outcome_cantons.no_half_cantons = 0;
+        assert_eq!(issue.is_accepted_computed(), Some(true));
+        issue.provisional = true;
+        assert_eq!(issue.is_accepted_computed(), None);
+        issue.provisional = false;
+        issue.issue_completed = false;
+        assert_eq!(issue.is_accepted_computed(), None);
+    }
+
+    #[test]
+    fn national_diff() {
+        let previous = national_data();
+        let mut current = previous.clone();
+        assert!(current.diff(&previous).is_empty());
+
+        let issue = &mut current.country.issues[0];
+        issue.cantons.reverse();
+        assert!(current.diff(&previous).is_empty());
+
+        let mut added = current.country.issues[0].clone();
+        added.issue_id = 6770;
+        current.country.issues.push(added);
+        let stans = &mut current.country.issues[0].cantons[0];
+        stans.outcome.count_completed = false;
+        let diff = current.diff(&previous);
+        assert_eq!(diff.added_issues, [6770]);
+        assert!(diff.removed_issues.is_empty());
+        assert_eq!(diff.changed_issues.len(), 1);
+        let changes = &diff.changed_issues[0].changes;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].level, GeoLevel::Canton);
+        assert_eq!(changes[0].geo_levelname, "Nidwalden");
+        assert!(!changes[0].completed());
+
+        let diff = previous.diff(&current);
+        assert_eq!(diff.removed_issues, [6770]);
+        assert!(diff.changed_issues[0].changes[0].completed());
+        assert!(serde_json::to_string(&diff).is_ok());
+    }
+
+    #[test]
+    fn cantonal_diff() {
+        let previous = cantonal_data();
+        let mut current = previous.clone();
+        current.kantone[1].issues.remove(1);
+        let zurich = &mut current.kantone[0].issues[0];
+        zurich.communes.as_mut().unwrap()[1].outcome.yes_votes += 1;
+        let diff = current.diff(&previous);
+        assert_eq!(diff.removed_issues, [202]);
+        let changes = &diff.changed_issues[0].changes;
+        assert_eq!(diff.changed_issues[0].issue_id, 101);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].level, GeoLevel::Commune);
+        assert_eq!(changes[0].geo_levelnumber, "261");
+        assert_eq!(changes[0].previous.unwrap().yes_votes, 550);
+        assert_eq!(changes[0].current.yes_votes, 551);
+    }
+
+    #[test]
+    fn voting_day_all_issues() {
+        let day = VotingDay {
+            national: national_data(),
+            cantonal: cantonal_data(),
+        };
+        let ids: Vec<_> = day
+            .all_issues()
+            .into_iter()
+            .map(|issue| match issue {
+                IssueRef::National(issue) => (0, issue.issue_id),
+                IssueRef::Cantonal(canton, issue) => (canton, issue.issue_id),
+            })
+            .collect();
+        assert_eq!(ids, [(0, 6760), (1, 101), (2, 201), (2, 202)]);
+    }
 
+    #[cfg(feature = "net")]
     #[tokio::test]
     async fn national_by_url() {
         let url =
@@ -315,12 +4839,14 @@ mod tests {
         assert!(out.is_ok());
     }
 
+    #[cfg(feature = "net")]
     #[tokio::test]
     async fn national_latest() {
         let out = national::get_latest().await;
         assert!(out.is_ok());
     }
 
+    #[cfg(feature = "net")]
     #[tokio::test]
     async fn cantonal_by_url() {
         let url =
@@ -329,6 +4855,7 @@ mod tests {
         assert!(out.is_ok());
     }
 
+    #[cfg(feature = "net")]
     #[tokio::test]
     async fn cantonal_latest() {
         let out = cantonal::get_latest().await;